@@ -1,11 +1,44 @@
 
 precision mediump float;
 varying vec2 v_textCoord; 
+varying vec3 v_normal;
+varying vec3 v_position;
+varying float v_sticker;
 
 uniform sampler2D u_texture0; //spriteSheet
 
+uniform bool u_lighting;        //If false then draw flat unlit colours
+uniform vec3 u_lightDirection;  //Direction the light shines in (camera is at origin looking down -z)
+uniform float u_ambient;        //Amount of light reaching sides facing away from the light
+uniform bool u_hidden;          //If true then stickers are greyed out (for blindfolded solving)
+
+const vec3 PLASTIC_COLOUR = vec3(0.05, 0.05, 0.05);
+const vec3 HIDDEN_COLOUR = vec3(0.45, 0.45, 0.45);
+const float SHININESS = 32.0;
+const float SPECULAR_STRENGTH = 0.35;
+
 void main(void) { 
 
-    gl_FragColor = texture2D(u_texture0, v_textCoord);
+    vec4 colour = texture2D(u_texture0, v_textCoord);
+
+    if (u_hidden) {
+        colour = vec4(mix(colour.rgb, HIDDEN_COLOUR, v_sticker), colour.a);
+    }
+
+    if (!u_lighting) {
+        gl_FragColor = colour;
+        return;
+    }
+
+    //Stickers are textured, the rest of the cubie is black plastic
+    vec3 base = mix(PLASTIC_COLOUR, colour.rgb, v_sticker);
+
+    vec3 normal = normalize(v_normal);
+    vec3 toLight = normalize(-u_lightDirection);
+    vec3 toCamera = normalize(-v_position);
+
+    float diffuse = max(dot(normal, toLight), 0.0);
+    float specular = pow(max(dot(reflect(-toLight, normal), toCamera), 0.0), SHININESS) * SPECULAR_STRENGTH;
+
+    gl_FragColor = vec4(base * (u_ambient + (1.0 - u_ambient) * diffuse) + vec3(specular), 1.0);
 }
-        
\ No newline at end of file
@@ -1,13 +1,36 @@
-
+        
 attribute vec3 a_coords;
-attribute vec2 a_textCoord;
+attribute vec3 a_normal;
+attribute vec2 a_faceCoord;     //Position across the side of the cubie (0.0 to 1.0)
+attribute float a_side;         //Side of the cubie (back, front, left, right, bottom, top)
+attribute float a_sticker;
+
+attribute mat4 a_model;         //Per cubie instance
+attribute vec3 a_tilesA;        //Sprite sheet tile for back, front and left sides (per cubie instance)
+attribute vec3 a_tilesB;        //Sprite sheet tile for right, bottom and top sides (per cubie instance)
 
 varying vec2 v_textCoord; 
+varying vec3 v_normal;
+varying vec3 v_position;
+varying float v_sticker;
 
-uniform mat4 u_model;
 uniform mat4 u_projection;
+uniform vec2 u_atlasSize;       //Number of columns and rows of tiles in the sprite sheet
 
 void main(void) {
-    v_textCoord = a_textCoord;
-    gl_Position = u_projection * u_model * vec4(a_coords, 1.0); 
+    vec4 position = a_model * vec4(a_coords, 1.0);
+
+    //Pick out the tile for this vertex's side, then find where that tile is in the sprite sheet
+    float tile = dot(a_tilesA, vec3(equal(vec3(a_side), vec3(0.0, 1.0, 2.0))))
+               + dot(a_tilesB, vec3(equal(vec3(a_side), vec3(3.0, 4.0, 5.0))));
+    float row = floor((tile + 0.5) / u_atlasSize.x);
+    float col = tile - row * u_atlasSize.x;
+
+    //Rows count down from the top of the sprite sheet, but texture coordinates count up from the bottom
+    v_textCoord = vec2((col + a_faceCoord.x) / u_atlasSize.x, 1.0 - (row + 1.0 - a_faceCoord.y) / u_atlasSize.y);
+    v_normal = (a_model * vec4(a_normal, 0.0)).xyz; //Model matrix only rotates and translates, so no normal matrix needed
+    v_position = position.xyz;
+    v_sticker = a_sticker;
+
+    gl_Position = u_projection * position; 
 }
@@ -0,0 +1,1047 @@
+
+use std::rc::Rc;          //Reference counter ie. smart pointer
+use std::cell::{Cell, RefCell};   //Reference cell ie. shared mutable memory
+
+use wasm_bindgen::prelude::*;
+use web_sys::{HtmlButtonElement, HtmlCanvasElement, WebGl2RenderingContext, WebGlProgram};
+
+use crate::blind::{BlindMemo, EdgeMethod, EdgeMethodName, Memo};
+use crate::blind_solution::{BlindMethod, BlindMethodName, BlindSolution, BlindSolutionObject};
+use crate::error::RubixError;
+use crate::events::{CubeEvent, CubeEventCallback, CubeEventType, Subscribers};
+use crate::fmc::{fmc_status, FmcStatus, InsertionResult, Insertions, Skeleton};
+use crate::history::{MoveHistory, SolveRecords};
+use crate::progress::{cross_progress, CrossProgressList};
+use crate::recognition::{recognise, CaseRecognition};
+use crate::reconstruction::Reconstruction;
+use crate::replay::{ReplayStatus, MAX_REPLAY_SPEED, MIN_REPLAY_SPEED};
+use crate::trainer::{Trainer, TrainerCard, TrainerCards, TrainerCases};
+use crate::renderer::CubieRenderer;
+use crate::notation::{format_moves, parse_moves};
+use crate::rubix::{Blindfold, Rubix, RotationDirection};
+use crate::scramble::{random_scramble, SCRAMBLE_LENGTH};
+use crate::step_solver::{solve_step, SolverStepName, Step, StepSolutionObject, MAX_STEP_DEPTH};
+use crate::state::CubeState;
+use crate::texture::{self, AtlasLayout};
+use crate::{get_current_time, log, request_animation_frame, shaders, webGL_context, window};
+
+//Shared handle to the closure which draws a frame then requests the next one
+type RenderLoop = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+//Browser callbacks registered by an app, which are removed when the app is destroyed
+type Listeners = Rc<RefCell<Vec<Listener>>>;
+
+//Longest time a single frame is allowed to advance the animation by (in seconds)
+const MAX_FRAME_TIME: f64 = 0.1;
+
+// Create struct to keep track of delta time for each frame
+struct DeltaTime {
+    past: f64,
+    current: f64,
+}
+
+impl DeltaTime {
+
+    // Method to move on to the current frame and get the time elapsed since the previous frame
+    fn tick(&mut self) -> f64 {
+        self.past = self.current;
+        self.current = get_current_time();
+        self.current - self.past
+    }
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const CUBE_APP_OPTIONS: &'static str = r#"
+export interface CubeAppOptions {
+    /** 6 css hex colours (red, orange, yellow, green, blue, white) the sticker atlas is drawn from */
+    stickerColours?: string[];
+    /** 6 face images as data urls (in the same order as the colours) to make a picture cube */
+    faceImages?: string[];
+    /** Button which switches the direction faces turn in, and shows the current direction */
+    directionButton?: HTMLButtonElement;
+}
+
+/** Direction faces turn in when their key is pressed */
+export type CubeRotationDirection = "clockwise" | "counterClockwise";
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CubeAppOptions")]
+    pub type CubeAppOptions;
+
+    #[wasm_bindgen(typescript_type = "CubeRotationDirection")]
+    pub type CubeRotationDirection;
+}
+
+// Options for a cube read from the plain JS object given to the CubeApp constructor
+#[derive(Default)]
+pub(crate) struct AppOptions {
+    pub stickerColours: Option<Vec<String>>,
+    pub faceImages: Option<Vec<String>>,
+    pub directionButton: Option<HtmlButtonElement>,
+}
+
+impl AppOptions {
+
+    fn from_js(options: &JsValue) -> Result<Self, RubixError>
+    {
+        if options.is_undefined() || options.is_null() { return Ok(Self::default()); }
+
+        let directionButton = match Self::field(options, "directionButton")? {
+            Some(button) => Some(button.dyn_into::<HtmlButtonElement>()
+                .map_err(|_| RubixError::InvalidOption("directionButton must be a button element".to_string()))?),
+            None => None,
+        };
+
+        Ok(Self {
+            stickerColours: Self::strings(options, "stickerColours")?,
+            faceImages: Self::strings(options, "faceImages")?,
+            directionButton,
+        })
+    }
+
+    // Helper function to get a field of the options object, treating undefined and null as missing
+    fn field(options: &JsValue, name: &str) -> Result<Option<JsValue>, RubixError>
+    {
+        let value = js_sys::Reflect::get(options, &JsValue::from_str(name))?;
+        Ok(if value.is_undefined() || value.is_null() { None } else { Some(value) })
+    }
+
+    // Helper function to get a field of the options object which must be an array of strings
+    fn strings(options: &JsValue, name: &str) -> Result<Option<Vec<String>>, RubixError>
+    {
+        let Some(value) = Self::field(options, name)? else { return Ok(None); };
+        let invalid = || RubixError::InvalidOption(format!("{} must be an array of strings", name));
+
+        if !js_sys::Array::is_array(&value) { return Err(invalid()); }
+
+        js_sys::Array::from(&value).iter()
+            .map(|item| item.as_string().ok_or_else(invalid))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+}
+
+// Where a callback was registered with the browser
+enum Registration {
+    Event(web_sys::EventTarget, &'static str),
+    Observer(web_sys::ResizeObserver),
+}
+
+// Callback registered with the browser, which is unregistered (and its closure freed) when dropped
+struct Listener {
+    registration: Registration,
+    callback: Box<dyn AsRef<JsValue>>,
+}
+
+impl Listener {
+
+    // Add an event listener to a target (eg. canvas or button)
+    fn event<T: ?Sized + 'static>(target: &web_sys::EventTarget, event: &'static str, callback: Closure<T>) -> Result<Self, RubixError>
+    {
+        target.add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())?;
+        Ok(Self { registration: Registration::Event(target.clone(), event), callback: Box::new(callback) })
+    }
+}
+
+impl Drop for Listener {
+
+    fn drop(&mut self)
+    {
+        match &self.registration {
+            Registration::Event(target, event) => {
+                target.remove_event_listener_with_callback(event, (*self.callback).as_ref().unchecked_ref()).ok();
+            },
+            Registration::Observer(observer) => observer.disconnect(),
+        }
+    }
+}
+
+/*
+ * A rubik's cube drawn to a canvas. Each app has its own webGL context, render loop and listeners (scoped to its
+ * canvas and direction button) so several independent cubes can live on the same page.
+ * The render loop keeps the app running even if the JS object is garbage collected, until destroy is called
+ */
+#[wasm_bindgen]
+pub struct CubeApp {
+    canvas: HtmlCanvasElement,
+    rubix: Rc<RefCell<Rubix>>,
+    gl_refcell: Rc<RefCell<WebGl2RenderingContext>>,
+    renderer_refcell: Rc<RefCell<Option<CubieRenderer>>>, // None while the webGL context is lost or once destroyed
+    render_loop: RenderLoop,
+    frame_id: Rc<Cell<i32>>,
+    listeners: Listeners,
+    subscribers: Rc<RefCell<Subscribers>>, // JS callbacks for the events of the cube
+    directionButton: Option<HtmlButtonElement>,
+    trainer: Rc<RefCell<Trainer>>,
+}
+
+#[wasm_bindgen]
+impl CubeApp {
+
+    /*
+     * Create a cube on the given canvas and start drawing it. Throws a RubixError if WebGL2 is unsupported,
+     * the options are invalid or the shaders or texture fail to load
+     */
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, options: Option<CubeAppOptions>) -> Result<CubeApp, JsValue>
+    {
+        let options = AppOptions::from_js(options.as_ref().map_or(&JsValue::UNDEFINED, |options| options))?;
+        Ok(Self::create(canvas, options)?)
+    }
+
+    // Canvas the cube is drawn to
+    #[wasm_bindgen(getter)]
+    pub fn canvas(&self) -> HtmlCanvasElement
+    {
+        self.canvas.clone()
+    }
+
+    // Method to switch between drawing lit bevelled cubies and flat unlit ones
+    #[wasm_bindgen(js_name = toggleStyle)]
+    pub fn toggleStyle(&self)
+    {
+        self.rubix.borrow_mut().toggleStyle();
+    }
+
+    /*
+     * Subscribe a callback to an event of the cube (see CubeEvent). Returns an id which can be passed to off
+     */
+    pub fn on(&self, eventType: CubeEventType, callback: CubeEventCallback) -> Result<u32, JsValue>
+    {
+        let eventType = eventType.as_string().unwrap_or_default();
+        let callback = callback.dyn_into::<js_sys::Function>()
+            .map_err(|_| RubixError::InvalidOption("Event callback must be a function".to_string()))?;
+
+        Ok(self.subscribers.borrow_mut().subscribe(&eventType, callback)?)
+    }
+
+    // Unsubscribe the callback with the given id. Returns false if no callback had that id
+    pub fn off(&self, id: u32) -> bool
+    {
+        self.subscribers.borrow_mut().unsubscribe(id)
+    }
+
+    // Instantly apply a random scramble (of 25 moves unless a length is given) to the cube. Returns the scramble in notation
+    pub fn scramble(&self, length: Option<usize>) -> String
+    {
+        let scramble = random_scramble(length.unwrap_or(SCRAMBLE_LENGTH), js_sys::Math::random);
+        self.rubix.borrow_mut().applyScramble(&scramble);
+        self.trainer.borrow_mut().abandon();
+
+        dispatchEvents(&self.rubix, &self.subscribers, &self.trainer);
+        format_moves(&scramble)
+    }
+
+    /*
+     * Queue an algorithm in standard notation (eg. "R U R' U'") to be animated after any moves already queued.
+     * Returns a promise which resolves once its last move has finished. Throws a RubixError if the notation is invalid
+     */
+    #[wasm_bindgen(js_name = applyAlgorithm)]
+    pub fn applyAlgorithm(&self, algorithm: &str) -> Result<js_sys::Promise, JsValue>
+    {
+        let moves = parse_moves(algorithm)?;
+        let movesQueued = self.rubix.borrow_mut().queueMoves(&moves);
+
+        Ok(self.waitFor(movesQueued))
+    }
+
+    // Returns a promise which resolves once every move queued so far has finished
+    #[wasm_bindgen(js_name = whenIdle)]
+    pub fn whenIdle(&self) -> js_sys::Promise
+    {
+        let (movesQueued, _) = self.rubix.borrow().moveCounts();
+        self.waitFor(movesQueued)
+    }
+
+    /*
+     * Set the cube to the state given as a 54 letter facelet string (faces in the order U R F D L B, each read
+     * left to right, top to bottom). Moves still queued are dropped. Throws a RubixError if the state is impossible
+     */
+    #[wasm_bindgen(js_name = setState)]
+    pub fn setState(&self, facelets: &str) -> Result<(), JsValue>
+    {
+        let state = CubeState::from_facelets(facelets)?;
+        self.rubix.borrow_mut().setState(state);
+        self.trainer.borrow_mut().abandon();
+
+        //Anything waiting on the dropped moves can now resolve
+        dispatchEvents(&self.rubix, &self.subscribers, &self.trainer);
+        Ok(())
+    }
+
+    // Current state of the cube as a 54 letter facelet string (see setState). Moves still animating are not included
+    #[wasm_bindgen(js_name = getState)]
+    pub fn getState(&self) -> String
+    {
+        self.rubix.borrow().state().to_facelets()
+    }
+
+    #[wasm_bindgen(js_name = isSolved)]
+    pub fn isSolved(&self) -> bool
+    {
+        self.rubix.borrow().isSolved()
+    }
+
+    // Moves made since the cube was last scrambled (or had its state set), with their move counts
+    pub fn history(&self) -> MoveHistory
+    {
+        self.rubix.borrow().history().to_js().unchecked_into()
+    }
+
+    // Records of every time the cube was solved from a scramble, ready to be exported (eg. with JSON.stringify)
+    #[wasm_bindgen(js_name = solveRecords)]
+    pub fn solveRecords(&self) -> SolveRecords
+    {
+        let rubix = self.rubix.borrow();
+        let records = rubix.solves().iter().map(|record| record.to_js()).collect::<js_sys::Array>();
+
+        records.unchecked_into()
+    }
+
+    /*
+     * Start a blindfolded solve: the stickers can be seen while inspecting, then are greyed out once the first move
+     * is made (or after the inspection time, in seconds, if one is given) until the cube is solved
+     */
+    #[wasm_bindgen(js_name = startBlindfolded)]
+    pub fn startBlindfolded(&self, inspectionTime: Option<f64>)
+    {
+        self.rubix.borrow_mut().startBlindfold(inspectionTime);
+    }
+
+    // End inspection of a blindfolded solve now, hiding the stickers
+    #[wasm_bindgen(js_name = hideStickers)]
+    pub fn hideStickers(&self)
+    {
+        self.rubix.borrow_mut().setBlindfold(Blindfold::On);
+    }
+
+    // Stop the blindfolded solve, showing the stickers again
+    #[wasm_bindgen(js_name = stopBlindfolded)]
+    pub fn stopBlindfolded(&self)
+    {
+        self.rubix.borrow_mut().setBlindfold(Blindfold::Off);
+    }
+
+    /*
+     * Memo of the cube in Speffz letters for a blindfolded solve, with the corners traced from the Old Pochmann buffer (UBL)
+     * and the edges from the buffer of the given method ("m2" for DF unless "oldPochmann" for UR). Throws a RubixError for other methods
+     */
+    #[wasm_bindgen(js_name = blindMemo)]
+    pub fn blindMemo(&self, method: Option<EdgeMethodName>) -> Result<BlindMemo, JsValue>
+    {
+        let method = match method.and_then(|method| method.as_string()).as_deref() {
+            None | Some("m2")   => EdgeMethod::M2,
+            Some("oldPochmann") => EdgeMethod::OldPochmann,
+            Some(other) => return Err(RubixError::InvalidOption(format!("Edge method must be \"oldPochmann\" or \"m2\", got \"{}\"", other)).into()),
+        };
+
+        Ok(Memo::new(self.rubix.borrow().state(), method).to_js().unchecked_into())
+    }
+
+    /*
+     * Blindfolded solution of the cube by "oldPochmann", "m2" (M2 edges, Old Pochmann corners) or "3style" commutators:
+     * each step's targets with the algorithm which solves them, and every move written out to do (eg. with applyAlgorithm)
+     * to check each target. Throws a RubixError for other methods
+     */
+    #[wasm_bindgen(js_name = blindSolution)]
+    pub fn blindSolution(&self, method: BlindMethodName) -> Result<BlindSolutionObject, JsValue>
+    {
+        let name = method.as_string().unwrap_or_default();
+        let method = BlindMethod::from_name(&name)
+            .ok_or_else(|| RubixError::InvalidOption(format!("Blind method must be \"oldPochmann\", \"m2\" or \"3style\", got \"{}\"", name)))?;
+
+        Ok(BlindSolution::new(self.rubix.borrow().state(), method).to_js().unchecked_into())
+    }
+
+    /*
+     * Find moves which solve a step of Roux ("firstBlock", "secondBlock", "cmll", "lse") or ZZ ("eoLine", "eoCross", "zzF2l")
+     * from the cube as it is, searching each part of the step up to maxDepth moves deep (at most 20). Gives undefined if
+     * nothing was found that short. Throws a RubixError for other steps, or if the steps before it aren't solved
+     */
+    #[wasm_bindgen(js_name = solveStep)]
+    pub fn solveStep(&self, step: SolverStepName, maxDepth: Option<usize>) -> Result<StepSolutionObject, JsValue>
+    {
+        let name = step.as_string().unwrap_or_default();
+        let step = Step::from_name(&name)
+            .ok_or_else(|| RubixError::InvalidOption(format!("Step must be \"firstBlock\", \"secondBlock\", \"cmll\", \"lse\", \"eoLine\", \"eoCross\" or \"zzF2l\", got \"{}\"", name)))?;
+
+        let maxDepth = maxDepth.unwrap_or(step.default_depth());
+        if maxDepth > MAX_STEP_DEPTH {
+            return Err(RubixError::InvalidOption(format!("Step search depth must be at most {}, got {}", MAX_STEP_DEPTH, maxDepth)).into());
+        }
+
+        let solution = solve_step(self.rubix.borrow().state(), step, maxDepth)?;
+        Ok(solution.map_or(JsValue::UNDEFINED, |solution| solution.to_js()).unchecked_into())
+    }
+
+    /*
+     * Start a fewest moves attempt: the cube is set to solved then scrambled (with the given scramble, else a random one),
+     * and an fmcTimeUp event is sent once the hour allowed is up. Returns the scramble. Throws a RubixError if the scramble is invalid
+     */
+    #[wasm_bindgen(js_name = startFmc)]
+    pub fn startFmc(&self, scramble: Option<String>) -> Result<String, JsValue>
+    {
+        let scramble = match scramble {
+            Some(scramble) => parse_moves(&scramble)?,
+            None => random_scramble(SCRAMBLE_LENGTH, js_sys::Math::random),
+        };
+
+        {
+            let mut rubix = self.rubix.borrow_mut();
+            rubix.setState(CubeState::solved());
+            rubix.applyScramble(&scramble);
+            rubix.startFmc();
+        }
+        self.trainer.borrow_mut().abandon();
+
+        dispatchEvents(&self.rubix, &self.subscribers, &self.trainer);
+        Ok(format_moves(&scramble))
+    }
+
+    // Stop the time limit of the FMC attempt (the moves made are kept)
+    #[wasm_bindgen(js_name = stopFmc)]
+    pub fn stopFmc(&self)
+    {
+        self.rubix.borrow_mut().stopFmc();
+    }
+
+    /*
+     * Switch between making moves on the scramble and on the inverse scramble (NISS). The cube is shown from the other side
+     * with the moves made there so far, and moves still queued are dropped. Returns true if moves are now on the inverse
+     */
+    #[wasm_bindgen(js_name = switchNiss)]
+    pub fn switchNiss(&self) -> bool
+    {
+        self.rubix.borrow_mut().switchNiss()
+    }
+
+    // Scramble, skeleton (with inverse moves in brackets) and time left of the FMC attempt, or undefined if none was started
+    #[wasm_bindgen(js_name = fmcStatus)]
+    pub fn fmcStatus(&self) -> FmcStatus
+    {
+        let rubix = self.rubix.borrow();
+        let status = rubix.fmc().map(|timer| fmc_status(rubix.history(), timer, get_current_time()));
+
+        status.unwrap_or(JsValue::UNDEFINED).unchecked_into()
+    }
+
+    /*
+     * Find commutators to insert into a skeleton (eg. "R U (F' D)", with moves on the inverse in brackets) to solve the
+     * 3-cycles it leaves from the state the cube was last scrambled (or set) to. Uses the moves made since the cube was last scrambled if no skeleton is given.
+     * Throws a RubixError if the skeleton is invalid
+     */
+    #[wasm_bindgen(js_name = findInsertions)]
+    pub fn findInsertions(&self, skeleton: Option<String>) -> Result<InsertionResult, JsValue>
+    {
+        let rubix = self.rubix.borrow();
+        let skeleton = match skeleton {
+            Some(skeleton) => Skeleton::parse(&skeleton)?,
+            None => rubix.history().skeleton(),
+        };
+
+        Ok(Insertions::find(&rubix.history().start, &skeleton.solution()).to_js().unchecked_into())
+    }
+
+    /*
+     * Make an alg.cubing.net link to a reconstruction of a solve (by its index in solveRecords) or, if none is given,
+     * of the moves made since the cube was last scrambled. The setup is the scramble, so throws a RubixError if the cube
+     * wasn't scrambled from solved (eg. it was set to a state) or there is no solve record with the given index
+     */
+    #[wasm_bindgen(js_name = reconstructionUrl)]
+    pub fn reconstructionUrl(&self, solve: Option<usize>) -> Result<String, JsValue>
+    {
+        let rubix = self.rubix.borrow();
+
+        let history = match solve {
+            Some(index) => rubix.solves().get(index).map(|record| &record.history)
+                .ok_or_else(|| RubixError::InvalidOption(format!("No solve record {}", index)))?,
+            None => rubix.history(),
+        };
+
+        Ok(Reconstruction::of(history)?.to_url())
+    }
+
+    /*
+     * Load a reconstruction from an alg.cubing.net or twizzle link (or its query string eg. location.search):
+     * the cube is set to solved with the setup applied as its scramble, then the solution is animated.
+     * Returns a promise which resolves once the solution has finished. Throws a RubixError if either algorithm is invalid
+     */
+    #[wasm_bindgen(js_name = loadReconstruction)]
+    pub fn loadReconstruction(&self, url: &str) -> Result<js_sys::Promise, JsValue>
+    {
+        let reconstruction = Reconstruction::from_url(url)?;
+        let (setup, solution) = (parse_moves(&reconstruction.setup)?, parse_moves(&reconstruction.alg)?);
+
+        let movesQueued = {
+            let mut rubix = self.rubix.borrow_mut();
+            rubix.setState(CubeState::solved());
+            rubix.applyScramble(&setup);
+            rubix.queueMoves(&solution)
+        };
+        self.trainer.borrow_mut().abandon();
+
+        dispatchEvents(&self.rubix, &self.subscribers, &self.trainer);
+        Ok(self.waitFor(movesQueued))
+    }
+
+    /*
+     * Replay a solve (by its index in solveRecords) or, if none is given, the moves made since the cube was last scrambled.
+     * The moves are animated with the timing they were made with, from the state they were made from (on the inverse scramble
+     * for moves made there with NISS). Moves still queued
+     * are dropped. The cube goes back to its own state once the replay is stopped, or it is moved, scrambled or set.
+     * Throws a RubixError if there is no solve record with the given index
+     */
+    pub fn replay(&self, solve: Option<usize>) -> Result<(), JsValue>
+    {
+        {
+            let mut rubix = self.rubix.borrow_mut();
+
+            let history = match solve {
+                Some(index) => rubix.solves().get(index).map(|record| record.history.clone())
+                    .ok_or_else(|| RubixError::InvalidOption(format!("No solve record {} to replay", index)))?,
+                None => rubix.history().clone(),
+            };
+            rubix.startReplay(&history);
+        }
+        self.trainer.borrow_mut().abandon();
+
+        //Anything waiting on the dropped moves can now resolve
+        dispatchEvents(&self.rubix, &self.subscribers, &self.trainer);
+        Ok(())
+    }
+
+    // Stop the replay (if there is one) and go back to the cube's own state
+    #[wasm_bindgen(js_name = stopReplay)]
+    pub fn stopReplay(&self)
+    {
+        self.rubix.borrow_mut().stopReplay();
+    }
+
+    #[wasm_bindgen(js_name = pauseReplay)]
+    pub fn pauseReplay(&self)
+    {
+        if let Some(replay) = self.rubix.borrow_mut().replay_mut() { replay.setPaused(true); }
+    }
+
+    // Carry on playing the replay, starting again if it had reached the end
+    #[wasm_bindgen(js_name = playReplay)]
+    pub fn playReplay(&self)
+    {
+        if let Some(replay) = self.rubix.borrow_mut().replay_mut() { replay.setPaused(false); }
+    }
+
+    // Jump the replay to a time (in seconds of the recorded solve, see replayStatus)
+    #[wasm_bindgen(js_name = seekReplay)]
+    pub fn seekReplay(&self, position: f64)
+    {
+        if let Some(replay) = self.rubix.borrow_mut().replay_mut() { replay.seek(position); }
+    }
+
+    // Pause the replay and step forwards (or backwards, if negative) by a number of moves
+    #[wasm_bindgen(js_name = stepReplay)]
+    pub fn stepReplay(&self, moves: i32)
+    {
+        if let Some(replay) = self.rubix.borrow_mut().replay_mut() { replay.step(moves); }
+    }
+
+    // Set how fast the replay plays, from 0.25 to 4 times as fast as the moves were made. Throws a RubixError if out of range
+    #[wasm_bindgen(js_name = setReplaySpeed)]
+    pub fn setReplaySpeed(&self, speed: f64) -> Result<(), JsValue>
+    {
+        if !(MIN_REPLAY_SPEED..=MAX_REPLAY_SPEED).contains(&speed) {
+            return Err(RubixError::InvalidOption(format!("Replay speed must be from {} to {}, got {}", MIN_REPLAY_SPEED, MAX_REPLAY_SPEED, speed)).into());
+        }
+
+        if let Some(replay) = self.rubix.borrow_mut().replay_mut() { replay.setSpeed(speed); }
+        Ok(())
+    }
+
+    // Where the replay is up to (eg. to draw a scrub bar), or undefined if nothing is being replayed
+    #[wasm_bindgen(js_name = replayStatus)]
+    pub fn replayStatus(&self) -> ReplayStatus
+    {
+        let rubix = self.rubix.borrow();
+        rubix.replay().map_or(JsValue::UNDEFINED, |replay| replay.to_js()).unchecked_into()
+    }
+
+    /*
+     * Recognise the OLL or PLL case on the last layer, along with an algorithm which solves it from how the cube is held.
+     * Returns undefined unless the first two layers are solved (on any side)
+     */
+    #[wasm_bindgen(js_name = recognizeCase)]
+    pub fn recognizeCase(&self) -> CaseRecognition
+    {
+        let recognition = recognise(self.rubix.borrow().state());
+        recognition.map_or(JsValue::UNDEFINED, |recognition| recognition.to_js()).unchecked_into()
+    }
+
+    // Progress of the cross and F2L pairs for every cross colour (also sent as a progress event after every move)
+    #[wasm_bindgen(js_name = crossProgress)]
+    pub fn crossProgress(&self) -> CrossProgressList
+    {
+        let progress = cross_progress(self.rubix.borrow().state());
+        progress.iter().map(|colour| colour.to_js()).collect::<js_sys::Array>().unchecked_into()
+    }
+
+    /*
+     * Set up the next case to practise on the cube (from the given sets eg. ["oll", "pll"], or from every set) and start timing it.
+     * Cases are picked by spaced repetition. Solving the case sends a caseTrained event, as does skipping it (by calling
+     * nextCase again or skipCase). Returns undefined if there are no cases in the sets
+     */
+    #[wasm_bindgen(js_name = nextCase)]
+    pub fn nextCase(&self, sets: Option<Vec<String>>) -> TrainerCard
+    {
+        let skipped = self.trainer.borrow_mut().skip(js_sys::Date::now());
+        let next = self.trainer.borrow_mut().next(&sets.unwrap_or_default(), get_current_time(), js_sys::Math::random);
+
+        {
+            let mut rubix = self.rubix.borrow_mut();
+            if let Some(result) = skipped { rubix.pushEvent(CubeEvent::CaseTrained(result)); }
+
+            //Cases are set up from solved by undoing their algorithm
+            if let Some((_, setup)) = next.as_ref() {
+                rubix.setState(CubeState::solved());
+                rubix.applyScramble(setup);
+            }
+        }
+
+        dispatchEvents(&self.rubix, &self.subscribers, &self.trainer);
+        next.map_or(JsValue::UNDEFINED, |(card, _)| card.to_js()).unchecked_into()
+    }
+
+    // Give up on the case being practised, which counts as a failure so it comes back sooner
+    #[wasm_bindgen(js_name = skipCase)]
+    pub fn skipCase(&self)
+    {
+        if let Some(result) = self.trainer.borrow_mut().skip(js_sys::Date::now()) {
+            self.rubix.borrow_mut().pushEvent(CubeEvent::CaseTrained(result));
+        }
+        dispatchEvents(&self.rubix, &self.subscribers, &self.trainer);
+    }
+
+    /*
+     * Add cases to a set in the trainer, each with the algorithm which solves it. "oll", "pll", "f2l" and "zbll" are built in (ZBLL with two look algorithms,
+     * which can be swapped for your own by adding cases with the same names), other sets are empty until their cases are added here.
+     * Cases already in the set keep their progress. Throws a RubixError if an algorithm is invalid
+     */
+    #[wasm_bindgen(js_name = addTrainerCases)]
+    pub fn addTrainerCases(&self, set: &str, cases: TrainerCases) -> Result<(), JsValue>
+    {
+        let invalid = || RubixError::InvalidOption("Trainer cases must be an array of { name, algorithm } objects".to_string());
+        if !js_sys::Array::is_array(&cases) { return Err(invalid().into()); }
+
+        let cases = js_sys::Array::from(&cases).iter().map(|case| {
+            let string = |key: &str| js_sys::Reflect::get(&case, &JsValue::from_str(key)).ok().and_then(|value| value.as_string());
+            string("name").zip(string("algorithm")).ok_or_else(invalid)
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self.trainer.borrow_mut().add_cases(set, &cases)?)
+    }
+
+    // Every case in the trainer along with its progress
+    #[wasm_bindgen(js_name = trainerCards)]
+    pub fn trainerCards(&self) -> TrainerCards
+    {
+        let trainer = self.trainer.borrow();
+        trainer.cards().iter().map(|card| card.to_js()).collect::<js_sys::Array>().unchecked_into()
+    }
+
+    // Set how fast layers turn, in quarter turns per second. Throws a RubixError unless the speed is above zero
+    #[wasm_bindgen(js_name = setTurnSpeed)]
+    pub fn setTurnSpeed(&self, quarterTurnsPerSecond: f32) -> Result<(), JsValue>
+    {
+        if !(quarterTurnsPerSecond > 0.0 && quarterTurnsPerSecond.is_finite()) {
+            return Err(RubixError::InvalidOption(format!("Turn speed must be above zero, got {}", quarterTurnsPerSecond)).into());
+        }
+
+        self.rubix.borrow_mut().setTurnSpeed(quarterTurnsPerSecond);
+        Ok(())
+    }
+
+    // Set the direction faces turn in when their key is pressed (also updates the label of the direction button)
+    #[wasm_bindgen(js_name = setRotationDirection)]
+    pub fn setRotationDirection(&self, direction: CubeRotationDirection) -> Result<(), JsValue>
+    {
+        let direction = match direction.as_string().as_deref() {
+            Some("clockwise")        => RotationDirection::Clockwise,
+            Some("counterClockwise") => RotationDirection::CounterClockwise,
+            _ => return Err(RubixError::InvalidOption("Rotation direction must be \"clockwise\" or \"counterClockwise\"".to_string()).into()),
+        };
+
+        self.rubix.borrow_mut().setRotationDirection(direction);
+
+        if let Some(button) = self.directionButton.as_ref() {
+            setDirectionLabel(button, direction);
+        }
+        Ok(())
+    }
+
+    /*
+     * Stop drawing the cube, remove every listener it added and free everything it created on the GPU.
+     * The canvas is left as it is, and calling destroy again does nothing
+     */
+    pub fn destroy(&self)
+    {
+        //Stop render loop, then drop its closure which breaks the reference cycle keeping everything alive
+        window().cancel_animation_frame(self.frame_id.get()).ok();
+        self.render_loop.borrow_mut().take();
+
+        //Remove event listeners and the resize observer (freeing their closures), along with any JS callbacks
+        self.listeners.borrow_mut().clear();
+        let waiting = self.subscribers.borrow_mut().clear();
+
+        //Promises still waiting on moves resolve, since the moves will never be animated
+        for resolve in waiting {
+            resolve.call0(&JsValue::NULL).ok();
+        }
+
+        //Free everything on the GPU (already gone if the webGL context is lost)
+        if let Some(renderer) = self.renderer_refcell.borrow_mut().take() {
+            renderer.delete(&self.gl_refcell.borrow());
+        }
+    }
+}
+
+impl CubeApp {
+
+    // Helper function to get a promise which resolves once the given count of moves have finished
+    fn waitFor(&self, movesQueued: u64) -> js_sys::Promise
+    {
+        let (_, movesFinished) = self.rubix.borrow().moveCounts();
+        if movesQueued <= movesFinished { return js_sys::Promise::resolve(&JsValue::UNDEFINED); }
+
+        js_sys::Promise::new(&mut |resolve, _reject| self.subscribers.borrow_mut().wait(movesQueued, resolve))
+    }
+
+    pub(crate) fn create(canvas: HtmlCanvasElement, options: AppOptions) -> Result<CubeApp, RubixError>
+    {
+        //Get webGL context from Canvas element
+        let gl = webGL_context::init_webgl_context(&canvas)?;
+
+        //Picture cubes need a different tile on every side of every cubie
+        let layout = if options.faceImages.is_some() { AtlasLayout::Pictures } else { AtlasLayout::Colours };
+
+        //Get image for texture atlas for cube (either drawn in rust or decoded from the png embedded in the module)
+        //Kept on the rust side so the texture can be uploaded again if the webGL context is lost
+        let atlas = Rc::new(match (options.faceImages, options.stickerColours) {
+            (Some(images), _) => texture::PictureAtlas::from_data_urls(&images)?.build(),
+            (None, Some(colours)) => texture::StickerAtlas::from_hex_colours(&colours)?.build(),
+            (None, None) => texture::decode_png_bytes(include_bytes!("../assets/textures/rubix_cubes.png"))?,
+        });
+
+        //Create shaders, texture and cubie geometry on the GPU
+        let renderer_refcell = Rc::new(RefCell::new(Some(setupRenderer(&gl, &canvas, &atlas, layout)?)));
+
+        //Create rubixs cube, then get ref cell wrapped smart pointer
+        let rubix = Rubix::new([0.0, 0.0, -15.0], layout);
+        let rubix_refcell = Rc::new(RefCell::new(rubix));
+
+        let listeners: Listeners = Rc::new(RefCell::new(Vec::new()));
+        let subscribers = Rc::new(RefCell::new(Subscribers::default()));
+        let trainer = Rc::new(RefCell::new(Trainer::load()));
+
+        //Add event listener for keyboard input
+        listeners.borrow_mut().push(addKeyboardEventListener(&canvas, Rc::clone(&rubix_refcell), Rc::clone(&subscribers), Rc::clone(&trainer))?);
+
+        //Add event listener for button input
+        if let Some(button) = options.directionButton.clone() {
+            listeners.borrow_mut().push(addButtonEventListener(button, Rc::clone(&rubix_refcell))?);
+        }
+
+        //Keep track of the size the canvas is displayed at (in css pixels) so its drawing buffer can be resized to match
+        let canvas_size = Rc::new(Cell::new((canvas.client_width() as f64, canvas.client_height() as f64)));
+        listeners.borrow_mut().push(addResizeObserver(&canvas, Rc::clone(&canvas_size))?);
+
+        //Get shared reference to gl context
+        let gl_refcell = Rc::new(RefCell::new(gl));
+
+        //Beginning of render loop
+        let mut delta = DeltaTime { past : 0.0, current: get_current_time() };
+
+        //Create rc to refcell of closure which is the render loop
+        let f: RenderLoop = Rc::new( RefCell::new( None ) );
+        let g = Rc::clone(&f);
+
+        //Id of the next requested animation frame, so it can be cancelled
+        let frame_id = Rc::new(Cell::new(0));
+        let next_frame_id = Rc::clone(&frame_id);
+
+        //Add event listeners to pause the render loop if the webGL context is lost and rebuild everything once it is restored
+        let contextListeners = addContextLossEventListeners(&canvas, Rc::clone(&gl_refcell), Rc::clone(&renderer_refcell), Rc::clone(&atlas), layout, Rc::clone(&f), Rc::clone(&frame_id))?;
+        listeners.borrow_mut().extend(contextListeners);
+
+        let loop_canvas = canvas.clone();
+        let loop_rubix = Rc::clone(&rubix_refcell);
+        let loop_gl_refcell = Rc::clone(&gl_refcell);
+        let loop_renderer_refcell = Rc::clone(&renderer_refcell);
+        let loop_listeners = Rc::clone(&listeners);
+        let loop_subscribers = Rc::clone(&subscribers);
+        let loop_trainer = Rc::clone(&trainer);
+
+        *g.borrow_mut() = Some( Closure::wrap( Box::new( move || {  // Closure struct comes from wasm-bindgen crate
+                                                                    // converts rust closure to javascript closure
+            //Update delta time (capped, so the cube doesn't jump after the loop has been paused)
+            let elapsed = delta.tick().min(MAX_FRAME_TIME);
+
+            {
+                //Stop looping while the webGL context is lost (the loop is restarted when it is restored)
+                let renderer = loop_renderer_refcell.borrow();
+                let Some(renderer) = renderer.as_ref() else { return; };
+
+                //Match drawing buffer to the size of the canvas on screen (also picks up changes to the device pixel ratio)
+                resizeCanvas(&loop_canvas, &loop_gl_refcell.borrow(), renderer, canvas_size.get());
+
+                //Clear frame to clear colour
+                loop_gl_refcell.borrow().clear(WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+
+                //Render rubik's cube
+                loop_rubix.borrow_mut().draw(&loop_gl_refcell, renderer, elapsed);
+            }
+
+            //Tell JS about moves finished this frame (once nothing is borrowed, since callbacks may call back into the app)
+            dispatchEvents(&loop_rubix, &loop_subscribers, &loop_trainer);
+
+            //Listeners live as long as the render loop
+            let _ = &loop_listeners;
+
+            //Call self again so as to perpetually loop (unless the app has been destroyed)
+            if let Some(f) = f.borrow().as_ref() {
+                next_frame_id.set(request_animation_frame(f));
+            }
+
+        }) as Box<dyn FnMut()>));
+
+        //Make first animation call
+        frame_id.set(request_animation_frame(g.borrow().as_ref().unwrap()));
+
+        Ok(CubeApp {
+            canvas,
+            rubix: rubix_refcell,
+            gl_refcell,
+            renderer_refcell,
+            render_loop: g,
+            frame_id,
+            listeners,
+            subscribers,
+            directionButton: options.directionButton,
+            trainer,
+        })
+    }
+}
+
+/*
+ * Function to send the events a cube has queued up to the JS callbacks subscribed to them
+ */
+fn dispatchEvents(rubix_refcell: &RefCell<Rubix>, subscribers: &RefCell<Subscribers>, trainer: &RefCell<Trainer>)
+{
+    //Nothing stays borrowed while calling JS, as callbacks are free to call back into the app
+    let (mut events, (_, movesFinished)) = {
+        let mut rubix = rubix_refcell.borrow_mut();
+        (rubix.take_events(), rubix.moveCounts())
+    };
+
+    //Solving the cube finishes any case being practised in the trainer
+    if events.contains(&CubeEvent::Solved) {
+        if let Some(result) = trainer.borrow_mut().finish(rubix_refcell.borrow().history(), js_sys::Date::now()) {
+            events.push(CubeEvent::CaseTrained(result));
+        }
+    }
+
+    for event in events {
+
+        let payload = event.to_js();
+        let callbacks = subscribers.borrow().callbacks_for(&event);
+
+        for callback in callbacks {
+            if let Err(e) = callback.call1(&JsValue::NULL, &payload) {
+                crate::error(&format!("Error: CubeApp:: Event callback threw {}", RubixError::from(e)));
+            }
+        }
+    }
+
+    //Resolve promises waiting on moves which have now finished
+    let finished = subscribers.borrow_mut().take_finished(movesFinished);
+    for resolve in finished {
+        resolve.call0(&JsValue::NULL).ok();
+    }
+}
+
+/*
+ * Function to create everything which lives on the GPU (ie. shaders, texture and cubie geometry) for a webGL context
+ */
+fn setupRenderer(gl: &WebGl2RenderingContext, canvas: &HtmlCanvasElement, atlas: &image::RgbaImage, layout: AtlasLayout)
+    -> Result<CubieRenderer, RubixError>
+{
+    //Get shader files as a string
+    let vertex_shader_str = include_str!("../assets/shaders/shad.vs");
+    let fragment_shader_str = include_str!("../assets/shaders/shad.fs");
+
+    //Assign shaders to current webGL context
+    let shader_program: WebGlProgram = shaders::setup_shaders(gl, vertex_shader_str, fragment_shader_str)?;
+
+    //Load image for texture atlas for cube
+    let texture = texture::load_texture_from_image(gl, atlas, 0)?;
+
+    //Upload geometry shared by every cubie
+    let renderer = CubieRenderer::new(gl, &shader_program, texture, layout)?;
+
+    //Set viewport and projection matrix for the current size of the canvas
+    renderer.resize(gl, canvas.width(), canvas.height());
+
+    //Set gl context flags
+    gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+    gl.depth_func(WebGl2RenderingContext::LEQUAL);
+
+    Ok(renderer)
+}
+
+/*
+ * Function to add event listeners for the webGL context being lost and restored
+ */
+fn addContextLossEventListeners(
+    canvas: &HtmlCanvasElement,
+    gl_refcell: Rc<RefCell<WebGl2RenderingContext>>,
+    renderer_refcell: Rc<RefCell<Option<CubieRenderer>>>,
+    atlas: Rc<image::RgbaImage>,
+    layout: AtlasLayout,
+    render_loop: RenderLoop,
+    frame_id: Rc<Cell<i32>>,
+) -> Result<[Listener; 2], RubixError>
+{
+    let lost_renderer_refcell = Rc::clone(&renderer_refcell);
+    let lost_frame_id = Rc::clone(&frame_id);
+
+    let lost_callback = Closure::<dyn FnMut(_)>::new( move |event: web_sys::Event| {
+
+        //Tell the browser we want the context restored
+        event.prevent_default();
+
+        //Pause render loop, then drop the renderer since everything on the GPU is gone
+        window().cancel_animation_frame(lost_frame_id.get()).ok();
+        lost_renderer_refcell.borrow_mut().take();
+        log("Main:: WebGL context lost");
+    });
+
+    let restored_canvas = canvas.clone();
+
+    let restored_callback = Closure::<dyn FnMut(_)>::new( move |_event: web_sys::Event| {
+
+        //Rubik's cube is untouched (it lives on the rust side) so only the GPU side needs rebuilding
+        match setupRenderer(&gl_refcell.borrow(), &restored_canvas, &atlas, layout) {
+            Ok(renderer) => *renderer_refcell.borrow_mut() = Some(renderer),
+            Err(e) => { crate::error(&format!("Error: Main:: Cannot restore WebGL context {}", e)); return; },
+        }
+
+        //Restart render loop
+        if let Some(f) = render_loop.borrow().as_ref() {
+            frame_id.set(request_animation_frame(f));
+        }
+    });
+
+    Ok([
+        Listener::event(canvas, "webglcontextlost", lost_callback)?,
+        Listener::event(canvas, "webglcontextrestored", restored_callback)?,
+    ])
+}
+
+/*
+ * Function to resize the canvas drawing buffer (and with it the viewport and projection) to match its displayed size
+ */
+fn resizeCanvas(canvas: &HtmlCanvasElement, gl: &WebGl2RenderingContext, renderer: &CubieRenderer, css_size: (f64, f64))
+{
+    //Drawing buffer is measured in device pixels so the cube stays sharp on high resolution screens
+    let pixel_ratio = window().device_pixel_ratio();
+    let width = (css_size.0 * pixel_ratio).round() as u32;
+    let height = (css_size.1 * pixel_ratio).round() as u32;
+
+    //Nothing to do if canvas is hidden or already the right size
+    if width == 0 || height == 0 || (width == canvas.width() && height == canvas.height()) { return; }
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+    renderer.resize(gl, width, height);
+}
+
+/*
+ * Function to add a resize observer which records the size the canvas is displayed at
+ */
+fn addResizeObserver(canvas: &HtmlCanvasElement, canvas_size: Rc<Cell<(f64, f64)>>) -> Result<Listener, RubixError>
+{
+    let callback = Closure::<dyn FnMut(_)>::new( move |entries: js_sys::Array| {
+
+        //Only the latest size matters
+        if let Ok(entry) = entries.at(-1).dyn_into::<web_sys::ResizeObserverEntry>() {
+            let rect = entry.content_rect();
+            canvas_size.set((rect.width(), rect.height()));
+        }
+    });
+
+    let observer = web_sys::ResizeObserver::new(callback.as_ref().unchecked_ref())?;
+    observer.observe(canvas);
+
+    Ok(Listener { registration: Registration::Observer(observer), callback: Box::new(callback) })
+}
+
+/*
+ * Function to add event listener for keypresses made while the canvas has focus
+ */
+fn addKeyboardEventListener(canvas: &HtmlCanvasElement, rubix_refcell: Rc<RefCell<Rubix>>, subscribers: Rc<RefCell<Subscribers>>, trainer: Rc<RefCell<Trainer>>) 
+    -> Result<Listener, RubixError>
+{
+    //Canvases can only take keyboard focus (eg. by being clicked on) once they have a tab index
+    if !canvas.has_attribute("tabindex") {
+        canvas.set_tab_index(0);
+    }
+
+    let callback = Closure::<dyn FnMut(_)>::new( move |event: web_sys::KeyboardEvent| {
+
+        match event.code().as_str() {
+
+            "ArrowLeft" | "ArrowRight" | "ArrowUp" | "ArrowDown" => rubix_refcell.borrow_mut().rotateCube( event.code() ),
+            "KeyR" | "KeyO" | "KeyY" | "KeyG" | "KeyB" | "KeyW"  => rubix_refcell.borrow_mut().rotateFace( event.code() ),
+            "KeyL" => { rubix_refcell.borrow_mut().toggleStyle(); },
+            _ => return, //Leave other keys (eg. tab) to the browser
+        }
+
+        event.prevent_default();
+        dispatchEvents(&rubix_refcell, &subscribers, &trainer);
+    });
+
+    //Add event listener for keyboard input
+    Listener::event(canvas, "keydown", callback)
+
+}
+
+/*
+ * Function to add event listener to button to change direction of rotation of rubik's cube faces
+ */
+fn addButtonEventListener(button: HtmlButtonElement, rubix_refcell: Rc<RefCell<Rubix>>) -> Result<Listener, RubixError>
+{
+    let target = button.clone();
+
+    let callback = Closure::<dyn FnMut(_)>::new( move |event: web_sys::Event| {
+
+        event.prevent_default();
+
+        let direction = rubix_refcell.borrow_mut().changeRotationDirection();
+        setDirectionLabel(&button, direction);
+    });
+
+    //Add event listener for button press
+    Listener::event(&target, "click", callback)
+
+}
+
+/*
+ * Function to show the current direction of rotation on the direction button
+ */
+fn setDirectionLabel(button: &HtmlButtonElement, direction: RotationDirection)
+{
+    match direction {
+
+        RotationDirection::Clockwise        => button.set_inner_text("Clockwise"),
+        RotationDirection::CounterClockwise => button.set_inner_text("Counter-Clockwise"),
+    }
+}
@@ -0,0 +1,356 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::permutation::{positions, PieceKind};
+use crate::progress::relative_colour;
+use crate::state::{cross, dot, slot_index, slot_position, CubeState, Face, IVec3};
+
+/*
+    Memo for blindfolded solving in the Speffz letter scheme: each corner and edge sticker has a letter from A to X,
+    going face by face (U L F R B D) clockwise from the top left of each face. Stickers are traced from a buffer piece,
+    with a cycle break (shooting to an unsolved piece and back) each time the buffer comes back before everything is solved.
+    Corners use the Old Pochmann buffer (UBL), edges the Old Pochmann (UR) or M2 (DF) buffer. Colours are taken relative to
+    the centers so the memo is for the cube as it is held
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const BLIND_MEMO: &'static str = r#"
+/** Method edges are solved with, which sets their buffer (UR for Old Pochmann, DF for M2) */
+export type EdgeMethod = "oldPochmann" | "m2";
+
+/**
+ * Speffz letters to memorise for a blindfolded solve. Breaks are the indexes of letters which start a cycle break,
+ * twisted corners and flipped edges are named by the letter of the sticker where the U or D (else F or B) colour is.
+ * Parity is an odd number of letters, so the corners and edges each need a parity algorithm
+ */
+export interface BlindMemo {
+    corners: string[];
+    edges: string[];
+    cornerBreaks: number[];
+    edgeBreaks: number[];
+    twistedCorners: string[];
+    flippedEdges: string[];
+    parity: boolean;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "EdgeMethod")]
+    pub type EdgeMethodName;
+
+    #[wasm_bindgen(typescript_type = "BlindMemo")]
+    pub type BlindMemo;
+}
+
+// Faces in the order Speffz letters them, then where corner and edge letters are on each face (clockwise from the top left)
+const SPEFFZ_FACES: [Face; 6] = [Face::U, Face::L, Face::F, Face::R, Face::B, Face::D];
+const CORNER_STICKERS: [usize; 4] = [0, 2, 8, 6];
+const EDGE_STICKERS: [usize; 4] = [1, 5, 7, 3];
+
+// Sticker of the corner corners are swapped with (UBL)
+pub const CORNER_BUFFER: char = 'A';
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeMethod { OldPochmann, M2 }
+
+impl EdgeMethod {
+
+    // Sticker of the piece pieces are swapped with
+    pub fn buffer(self) -> char
+    {
+        match self {
+            EdgeMethod::OldPochmann => 'B', // UR
+            EdgeMethod::M2          => 'U', // DF
+        }
+    }
+}
+
+// Letters of one kind of piece, with where the cycle breaks start and which pieces are only twisted (or flipped) in place
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub letters: Vec<char>,
+    pub breaks: Vec<usize>,
+    pub twisted: Vec<char>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Memo {
+    pub corners: Trace,
+    pub edges: Trace,
+}
+
+impl Memo {
+
+    pub fn new(state: &CubeState, method: EdgeMethod) -> Self
+    {
+        Self::traced(state, method, false)
+    }
+
+    // Memo where pieces twisted (or flipped) in place are solved with a cycle break (shooting to one sticker then another) like any other
+    pub fn with_twists(state: &CubeState, method: EdgeMethod) -> Self
+    {
+        Self::traced(state, method, true)
+    }
+
+    fn traced(state: &CubeState, method: EdgeMethod, breakTwisted: bool) -> Self
+    {
+        let mut edges = trace(state, PieceKind::Edge, method.buffer(), breakTwisted);
+
+        //M2 moves the M slice with every target, so the second of each pair of letters goes to the opposite M slice piece instead
+        if method == EdgeMethod::M2 {
+            for letter in edges.letters.iter_mut().skip(1).step_by(2) {
+                *letter = match *letter { 'C' => 'W', 'W' => 'C', 'I' => 'S', 'S' => 'I', letter => letter };
+            }
+        }
+
+        Self { corners: trace(state, PieceKind::Corner, CORNER_BUFFER, breakTwisted), edges }
+    }
+
+    // An odd number of targets leaves two corners and two edges swapped, which must be fixed with a parity algorithm
+    pub fn parity(&self) -> bool
+    {
+        self.corners.letters.len() % 2 == 1
+    }
+
+    // Method to build the plain JS object given to JS (see BlindMemo)
+    pub fn to_js(&self) -> JsValue
+    {
+        let letters = |letters: &[char]| letters.iter().map(|letter| JsValue::from_str(&letter.to_string())).collect::<js_sys::Array>();
+        let indexes = |breaks: &[usize]| breaks.iter().map(|i| JsValue::from(*i as u32)).collect::<js_sys::Array>();
+
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("corners", letters(&self.corners.letters).into());
+        set("edges", letters(&self.edges.letters).into());
+        set("cornerBreaks", indexes(&self.corners.breaks).into());
+        set("edgeBreaks", indexes(&self.edges.breaks).into());
+        set("twistedCorners", letters(&self.corners.twisted).into());
+        set("flippedEdges", letters(&self.edges.twisted).into());
+        set("parity", JsValue::from_bool(self.parity()));
+
+        object.into()
+    }
+}
+
+// Get the Speffz letter of a corner or edge sticker slot
+pub fn letter(slot: usize) -> Option<char>
+{
+    let face = SPEFFZ_FACES.iter().position(|face| face.index() == slot / 9)?;
+    let sticker = CORNER_STICKERS.iter().chain(EDGE_STICKERS.iter()).position(|i| *i == slot % 9)? % 4;
+
+    Some((b'A' + (face * 4 + sticker) as u8) as char)
+}
+
+// Get the slot of the sticker with a Speffz letter, for a kind of piece
+pub fn slot(letter: char, kind: PieceKind) -> usize
+{
+    let i = (letter as u8 - b'A') as usize;
+    let stickers = if kind == PieceKind::Corner { CORNER_STICKERS } else { EDGE_STICKERS };
+
+    SPEFFZ_FACES[i / 4].index() * 9 + stickers[i % 4]
+}
+
+// Follow the stickers of a kind of piece around from a buffer (given by its letter)
+fn trace(state: &CubeState, kind: PieceKind, buffer: char, breakTwisted: bool) -> Trace
+{
+    let mut trace = Trace::default();
+    let piece = |slot: usize| slot_position(slot).0;
+    let buffer = slot(buffer, kind);
+
+    //Pieces in the order of their first letter, so cycle breaks go to the earliest letter
+    let mut pieces: Vec<IVec3> = positions(kind);
+    pieces.sort_by_key(|position| slots(*position).into_iter().filter_map(letter).min());
+
+    let mut visited = vec![piece(buffer)];
+    let (mut current, mut start) = (buffer, piece(buffer));
+
+    loop {
+        let target = home_slot(state, current);
+
+        if piece(target) != start {
+            trace.letters.push(letter(target).unwrap_or('?'));
+            visited.push(piece(target));
+            current = target;
+            continue;
+        }
+
+        //Back to where the cycle started, which for a cycle break means shooting to its first piece again
+        if start != piece(buffer) { trace.letters.push(letter(target).unwrap_or('?')); }
+
+        let unsolved = pieces.iter().find(|position| !visited.contains(position) && !match breakTwisted {
+            true  => solved(state, **position),
+            false => in_place(state, **position),
+        });
+        let Some(next) = unsolved else { break; };
+
+        let first = slots(*next).into_iter().min_by_key(|slot| letter(*slot)).unwrap_or(buffer);
+        trace.breaks.push(trace.letters.len());
+        trace.letters.push(letter(first).unwrap_or('?'));
+
+        visited.push(*next);
+        (current, start) = (first, *next);
+    }
+
+    //Swap the buffer with each target to find the pieces left twisted (or flipped) in place, which can include the buffer
+    let mut stickers: Vec<usize> = (0..54).map(|slot| home_slot(state, slot)).collect();
+
+    for target in &trace.letters {
+        for (a, b) in turning(buffer).into_iter().zip(turning(slot(*target, kind))) { stickers.swap(a, b); }
+    }
+
+    for position in &pieces {
+        let reference = slot_index(*position, reference_face(*position));
+        let twisted = slots(*position).into_iter().find(|slot| stickers[*slot] == reference && *slot != reference);
+
+        if let Some(slot) = twisted { trace.twisted.push(letter(slot).unwrap_or('?')); }
+    }
+
+    trace
+}
+
+// Get the slots of a piece's stickers starting from the given one and going around the piece (always the same way round)
+pub fn turning(slot: usize) -> Vec<usize>
+{
+    let (position, face) = slot_position(slot);
+    let faces: Vec<Face> = Face::ALL.into_iter().filter(|face| dot(position, face.normal()) == 1).collect();
+    let mut around = vec![face];
+
+    while let Some(next) = faces.iter().find(|next| !around.contains(next) && (faces.len() == 2 || dot(cross(around[around.len() - 1].normal(), next.normal()), position) == 1)) {
+        around.push(*next);
+    }
+
+    around.into_iter().map(|face| slot_index(position, face)).collect()
+}
+
+// Get the slots of the stickers of the piece at a position
+fn slots(position: IVec3) -> Vec<usize>
+{
+    Face::ALL.into_iter().filter(|face| dot(position, face.normal()) == 1).map(|face| slot_index(position, face)).collect()
+}
+
+// Get the home slot of the sticker in a slot, from the colours of its piece relative to the centers
+fn home_slot(state: &CubeState, slot: usize) -> usize
+{
+    let (position, _) = slot_position(slot);
+    let home = slots(position).into_iter()
+        .map(|slot| relative_colour(state, slot).normal())
+        .fold([0, 0, 0], |home, normal| std::array::from_fn(|i| home[i] + normal[i]));
+
+    slot_index(home, relative_colour(state, slot))
+}
+
+// Check the piece at a position belongs there and is oriented
+fn solved(state: &CubeState, position: IVec3) -> bool
+{
+    slots(position).into_iter().all(|slot| home_slot(state, slot) == slot)
+}
+
+// Check the piece at a position belongs there (however it is oriented)
+fn in_place(state: &CubeState, position: IVec3) -> bool
+{
+    slots(position).into_iter().all(|slot| slot_position(home_slot(state, slot)).0 == position)
+}
+
+// Face of a position orientation is judged from: U or D, else F or B
+pub fn reference_face(position: IVec3) -> Face
+{
+    [Face::U, Face::D, Face::F, Face::B].into_iter().find(|face| dot(position, face.normal()) == 1).unwrap_or(Face::R)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_moves;
+    use crate::scramble::{random_scramble, seeded_random};
+
+    fn memo(algorithm: &str, method: EdgeMethod) -> Memo
+    {
+        Memo::new(&CubeState::solved().apply_moves(&parse_moves(algorithm).unwrap()), method)
+    }
+
+    #[test]
+    fn letters_name_every_sticker_once()
+    {
+        for kind in [PieceKind::Corner, PieceKind::Edge] {
+            let letters: Vec<char> = ('A'..='X').collect();
+            let slots: Vec<usize> = letters.iter().map(|letter| slot(*letter, kind)).collect();
+
+            assert_eq!(slots.iter().map(|slot| letter(*slot).unwrap()).collect::<Vec<_>>(), letters);
+        }
+
+        assert_eq!(slot('A', PieceKind::Corner), slot_index([-1, 1, -1], Face::U));
+        assert_eq!(slot('B', PieceKind::Edge), slot_index([1, 1, 0], Face::U));
+        assert_eq!(letter(Face::U.index() * 9 + 4), None);
+    }
+
+    #[test]
+    fn solved_cube_has_nothing_to_memorise()
+    {
+        //However the cube is held
+        for algorithm in ["", "x y", "z2 y'"] {
+            for method in [EdgeMethod::OldPochmann, EdgeMethod::M2] {
+                let memo = memo(algorithm, method);
+
+                assert_eq!(memo, Memo { corners: Trace::default(), edges: Trace::default() }, "{}", algorithm);
+                assert!(!memo.parity());
+            }
+        }
+    }
+
+    #[test]
+    fn traces_swaps_with_cycle_breaks()
+    {
+        //U2 swaps UBL with UFR and UBR with UFL, so corners need one break, as do edges from UR
+        let memo = memo("U2", EdgeMethod::OldPochmann);
+
+        assert_eq!(memo.corners, Trace { letters: vec!['C', 'B', 'D', 'B'], breaks: vec![1], twisted: Vec::new() });
+        assert_eq!(memo.edges, Trace { letters: vec!['D', 'A', 'C', 'A'], breaks: vec![1], twisted: Vec::new() });
+        assert!(!memo.parity());
+
+        //A quarter turn is an odd number of swaps
+        assert!(self::memo("U", EdgeMethod::OldPochmann).parity());
+
+        //M2 from DF, where the second of each pair of letters on the M slice goes to the opposite piece
+        let m2 = self::memo("U2", EdgeMethod::M2);
+        assert_eq!(m2.edges, Trace { letters: vec!['A', 'W', 'A', 'B', 'D', 'B'], breaks: vec![0, 3], twisted: Vec::new() });
+    }
+
+    #[test]
+    fn pieces_twisted_in_place_are_listed()
+    {
+        let twist = "[(R' D' R D)2, U]";
+        let memo = memo(twist, EdgeMethod::OldPochmann);
+
+        assert!(memo.corners.letters.is_empty());
+        assert_eq!(memo.corners.twisted.len(), 2);
+        assert!(memo.edges == Trace::default());
+
+        //Unless they are solved with cycle breaks
+        let broken = Memo::with_twists(&CubeState::solved().apply_moves(&parse_moves(twist).unwrap()), EdgeMethod::OldPochmann);
+        assert_eq!(broken.corners.letters.len(), 4);
+        assert_eq!(broken.corners.breaks, vec![0, 2]);
+        assert!(broken.corners.twisted.is_empty());
+
+        let flip = self::memo("M' U M' U M' U2 M U M U M U2", EdgeMethod::OldPochmann);
+        assert!(flip.edges.letters.is_empty());
+        assert_eq!(flip.edges.twisted.len(), 2);
+    }
+
+    #[test]
+    fn corner_and_edge_parity_match()
+    {
+        let mut random = seeded_random(47);
+
+        for _ in 0..50 {
+            let state = CubeState::solved().apply_moves(&random_scramble(20, &mut random));
+
+            for method in [EdgeMethod::OldPochmann, EdgeMethod::M2] {
+                let memo = Memo::new(&state, method);
+                assert_eq!(memo.corners.letters.len() % 2, memo.edges.letters.len() % 2);
+                assert!(memo.corners.letters.iter().chain(&memo.edges.letters).all(|letter| letter.is_ascii_uppercase()));
+            }
+        }
+    }
+}
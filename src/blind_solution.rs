@@ -0,0 +1,396 @@
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use wasm_bindgen::prelude::*;
+
+use crate::blind::{letter, slot, turning, EdgeMethod, Memo, CORNER_BUFFER};
+use crate::commutator::{commutator_layers, is_kind, pure_commutators};
+use crate::notation::{format_moves, parse_moves, Algorithm, Layers, Move, Node, Slice};
+use crate::permutation::PieceKind;
+use crate::state::{CubeState, Face};
+
+/*
+    Blindfolded solutions: the Speffz targets of the memo (see blind.rs) along with the moves which solve each one, so they can
+    be done on the cube and checked target by target. Swap methods bring each target to a helper position with setup moves,
+    swap it with the buffer then undo the setup: Old Pochmann swaps corners with a modified Y perm and edges with a T perm,
+    M2 swaps edges with M2. 3-style solves two targets at a time with a commutator (found by searching short commutators and
+    setups) from the Old Pochmann buffers. An odd number of targets is fixed with a parity algorithm.
+    Setups and commutators are found once, the first time a method is used
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const BLIND_SOLUTION: &'static str = r#"
+/** Method a blindfolded solution is made with: Old Pochmann, M2 edges with Old Pochmann corners, or 3-style commutators */
+export type BlindMethod = "oldPochmann" | "m2" | "3style";
+
+/** Moves which solve one or two targets (pieces are "edge" or "corner"), or fix parity (with no targets) */
+export interface BlindStep {
+    pieces: "edge" | "corner" | "parity";
+    targets: string;
+    algorithm: string;
+}
+
+/** Blindfolded solution of the cube: its steps in order, along with every move written out */
+export interface BlindSolution {
+    method: BlindMethod;
+    steps: BlindStep[];
+    moves: string;
+    moveCount: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "BlindMethod")]
+    pub type BlindMethodName;
+
+    #[wasm_bindgen(typescript_type = "BlindSolution")]
+    pub type BlindSolutionObject;
+}
+
+// Modified Y perm swapping the corner buffer (UBL) with RDF (and the UL and UB edges)
+const CORNER_SWAP: &str = "R U' R' U' R U R' F' R U R' U' R' F R";
+
+// T perm swapping the Old Pochmann edge buffer (UR) with UL (and the UFR and UBR corners)
+const EDGE_SWAP: &str = "R U R' U' R' F R2 U' R' U' R U R' F'";
+
+// Ra perm swapping the UFR and UBR corners and the UL and UB edges, to fix parity after the Old Pochmann edges
+const SWAP_PARITY: &str = "R U R' F' R U2 R' U2 R' F R U R U2 R' U'";
+
+// Fixes parity after M2 edges and Old Pochmann corners, which leave the M slice a half turn out as well as UL and UB swapped
+const M2_PARITY: &str = "D' L2 D M2 D' L2 D";
+
+// M2 targets on the M slice which can't be set up without moving it (or, for BU, flipping it), so have their own algorithms
+const M2_SPECIAL_CASES: [(char, &str); 5] = [
+    ('C', "U2 M' U2 M'"),
+    ('W', "M U2 M U2"),
+    ('I', "D M' U R2 U' M U R2 U' D' M2"),
+    ('S', "M2 D U R2 U' M' U R2 U' M D'"),
+    ('Q', "M2 F2 M' U M' U M' U2 M U M U M U2 F2"),
+];
+
+// Longest setup searched for (in moves)
+const MAX_SETUP: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlindMethod { OldPochmann, M2, ThreeStyle }
+
+impl BlindMethod {
+
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        match name {
+            "oldPochmann" => Some(BlindMethod::OldPochmann),
+            "m2"          => Some(BlindMethod::M2),
+            "3style"      => Some(BlindMethod::ThreeStyle),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str
+    {
+        match self {
+            BlindMethod::OldPochmann => "oldPochmann",
+            BlindMethod::M2          => "m2",
+            BlindMethod::ThreeStyle  => "3style",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub pieces: &'static str,
+    pub targets: String,
+    pub algorithm: Algorithm,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlindSolution {
+    pub method: BlindMethod,
+    pub steps: Vec<Step>,
+}
+
+impl BlindSolution {
+
+    pub fn new(state: &CubeState, method: BlindMethod) -> Self
+    {
+        let edgeMethod = if method == BlindMethod::M2 { EdgeMethod::M2 } else { EdgeMethod::OldPochmann };
+        let memo = Memo::with_twists(state, edgeMethod);
+
+        let mut steps = Vec::new();
+        let parity = memo.parity();
+
+        //Edges are solved first, so any parity is dealt with before the corners for the swap methods
+        let edges = if method == BlindMethod::M2 { m2_edges() } else { op_edges() };
+        match method {
+            BlindMethod::ThreeStyle => steps.extend(commutators(PieceKind::Edge, &memo.edges.letters, "edge", edges)),
+            _ => steps.extend(memo.edges.letters.iter().map(|target| edges.step("edge", *target))),
+        }
+
+        if parity && method != BlindMethod::M2 {
+            steps.push(Step { pieces: "parity", targets: String::new(), algorithm: parsed(SWAP_PARITY) });
+        }
+
+        match method {
+            BlindMethod::ThreeStyle => steps.extend(commutators(PieceKind::Corner, &memo.corners.letters, "corner", op_corners())),
+            _ => steps.extend(memo.corners.letters.iter().map(|target| op_corners().step("corner", *target))),
+        }
+
+        if parity && method == BlindMethod::M2 {
+            steps.push(Step { pieces: "parity", targets: String::new(), algorithm: parsed(M2_PARITY) });
+        }
+
+        Self { method, steps }
+    }
+
+    // Get every move of the solution written out
+    pub fn moves(&self) -> Vec<Move>
+    {
+        self.steps.iter().flat_map(|step| step.algorithm.expand()).collect()
+    }
+
+    // Method to build the plain JS object given to JS (see BlindSolution)
+    pub fn to_js(&self) -> JsValue
+    {
+        let steps = self.steps.iter().map(|step| {
+            let object = js_sys::Object::new();
+            let set = |key: &str, value: &str| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &JsValue::from_str(value)).ok(); };
+
+            set("pieces", step.pieces);
+            set("targets", &step.targets);
+            set("algorithm", &step.algorithm.to_string());
+            object
+        }).collect::<js_sys::Array>();
+
+        let moves = self.moves();
+
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("method", JsValue::from_str(self.method.name()));
+        set("steps", steps.into());
+        set("moves", JsValue::from_str(&format_moves(&moves)));
+        set("moveCount", JsValue::from(moves.len() as u32));
+
+        object.into()
+    }
+}
+
+/*
+    Swapping the buffer with any target: the target is set up to the helper position the swap algorithm swaps the buffer with,
+    by moves which leave alone everything else the swap algorithm moves
+*/
+struct Swap {
+    algorithm: Vec<Node>,
+    setups: HashMap<char, Vec<Move>>,   // Setup for each target letter
+    special: HashMap<char, Algorithm>, // Targets with their own algorithm instead
+}
+
+impl Swap {
+
+    fn new(kind: PieceKind, buffer: char, algorithm: &str, setupMoves: &[Layers], special: &[(char, &str)]) -> Self
+    {
+        let swap = parse_moves(algorithm).unwrap_or_default();
+        let swapped = CubeState::solved().apply_moves(&swap);
+
+        //Sticker which ends up in the buffer is the one the algorithm swaps with it
+        let buffer = slot(buffer, kind);
+        let helper = swapped.sticker(buffer);
+
+        //Setups mustn't move anything the swap moves other than the helper's piece
+        let helperPiece = turning(helper);
+        let kept: Vec<usize> = (0..54).filter(|s| swapped.sticker(*s) != *s && !helperPiece.contains(s)).collect();
+
+        let mut setups = HashMap::new();
+        search_setups(setupMoves, MAX_SETUP, |state, moves| {
+            if !kept.iter().all(|s| state.sticker(*s) == *s) { return; }
+
+            //Sticker which the setup brings to the helper is the target set up
+            if let Some(target) = letter(state.sticker(helper)).filter(|_| is_kind(state.sticker(helper), kind)) {
+                setups.entry(target).or_insert_with(|| moves.to_vec());
+            }
+        });
+
+        let special = special.iter().map(|(target, algorithm)| (*target, parsed(algorithm))).collect();
+
+        Self { algorithm: swap.into_iter().map(Node::Move).collect(), setups, special }
+    }
+
+    fn step(&self, pieces: &'static str, target: char) -> Step
+    {
+        let algorithm = match (self.special.get(&target), self.setups.get(&target)) {
+            (Some(algorithm), _)                    => algorithm.clone(),
+            (None, Some(setup)) if setup.is_empty() => Algorithm { nodes: self.algorithm.clone() },
+            (None, Some(setup))                     => Algorithm { nodes: vec![Node::Conjugate(setup.iter().map(|m| Node::Move(*m)).collect(), self.algorithm.clone(), 1)] },
+            (None, None)                            => unreachable!("no setup or special case for {} target {}", pieces, target),
+        };
+
+        Step { pieces, targets: target.to_string(), algorithm }
+    }
+}
+
+fn op_corners() -> &'static Swap
+{
+    static SWAP: OnceLock<Swap> = OnceLock::new();
+    SWAP.get_or_init(|| Swap::new(PieceKind::Corner, CORNER_BUFFER, CORNER_SWAP, &[Layers::Face(Face::R), Layers::Face(Face::D), Layers::Face(Face::F)], &[]))
+}
+
+fn op_edges() -> &'static Swap
+{
+    static SWAP: OnceLock<Swap> = OnceLock::new();
+    SWAP.get_or_init(|| Swap::new(PieceKind::Edge, EdgeMethod::OldPochmann.buffer(), EDGE_SWAP,
+        &[Layers::Face(Face::L), Layers::Face(Face::D), Layers::Slice(Slice::M), Layers::Slice(Slice::E)], &[]))
+}
+
+fn m2_edges() -> &'static Swap
+{
+    static SWAP: OnceLock<Swap> = OnceLock::new();
+    SWAP.get_or_init(|| Swap::new(PieceKind::Edge, EdgeMethod::M2.buffer(), "M2",
+        &Face::ALL.map(Layers::Face), &M2_SPECIAL_CASES))
+}
+
+/*
+    Commutators cycling the buffer with two targets, by target pair. Found by setting up the pure commutators (see pure_commutators)
+    with up to two moves to every position
+*/
+fn commutator_table(kind: PieceKind) -> &'static HashMap<(char, char), Algorithm>
+{
+    static CORNERS: OnceLock<HashMap<(char, char), Algorithm>> = OnceLock::new();
+    static EDGES: OnceLock<HashMap<(char, char), Algorithm>> = OnceLock::new();
+
+    let (table, buffer) = match kind {
+        PieceKind::Corner => (&CORNERS, CORNER_BUFFER),
+        _                 => (&EDGES, EdgeMethod::OldPochmann.buffer()),
+    };
+
+    table.get_or_init(|| {
+        let buffer = turning(slot(buffer, kind));
+
+        let mut table: HashMap<(char, char), (usize, Algorithm)> = HashMap::new();
+
+        search_setups(&commutator_layers(kind), 2, |state, setup| {
+            let setupSources: [usize; 54] = std::array::from_fn(|s| state.sticker(s));
+            let mut undo = [0; 54];
+            for s in 0..54 { undo[setupSources[s]] = s; }
+
+            for commutator in pure_commutators(kind) {
+
+                //Setup, then the commutator, then undoing the setup
+                let cycle: [usize; 54] = std::array::from_fn(|s| setupSources[commutator.sources[undo[s]]]);
+
+                //Swapping the buffer with the first target then the second brings the second to the buffer and the first to the second
+                let second = turning(cycle[buffer[0]]);
+                let first = turning(cycle[second[0]]);
+                let cycles = (0..buffer.len()).all(|i| cycle[buffer[i]] == second[i] && cycle[second[i]] == first[i] && cycle[first[i]] == buffer[i]);
+
+                let (Some(firstLetter), Some(secondLetter)) = (letter(first[0]), letter(second[0])) else { continue; };
+                if !cycles || first == buffer { continue; }
+
+                let commutator = commutator.node();
+                let nodes = if setup.is_empty() { vec![commutator] } else {
+                    vec![Node::Conjugate(setup.iter().map(|m| Node::Move(*m)).collect(), vec![commutator], 1)]
+                };
+                let length = setup.len() * 2 + 8;
+
+                if table.get(&(firstLetter, secondLetter)).is_none_or(|(best, _)| length < *best) {
+                    table.insert((firstLetter, secondLetter), (length, Algorithm { nodes }));
+                }
+            }
+        });
+
+        table.into_iter().map(|(targets, (_, algorithm))| (targets, algorithm)).collect()
+    })
+}
+
+// Steps solving targets two at a time with commutators, or with two swaps if no commutator was found. The last odd target is swapped
+fn commutators(kind: PieceKind, letters: &[char], pieces: &'static str, swap: &Swap) -> Vec<Step>
+{
+    let table = commutator_table(kind);
+
+    letters.chunks(2).map(|targets| match targets {
+        [first, second] => {
+            let algorithm = table.get(&(*first, *second)).cloned().unwrap_or_else(|| {
+                let nodes = [swap.step(pieces, *first), swap.step(pieces, *second)].into_iter().flat_map(|step| step.algorithm.nodes).collect();
+                Algorithm { nodes }
+            });
+            Step { pieces, targets: format!("{}{}", first, second), algorithm }
+        },
+        _ => swap.step(pieces, targets[0]),
+    }).collect()
+}
+
+// Call a function with every state (from solved) reached by up to a number of moves, along with the moves, shortest first
+fn search_setups(layers: &[Layers], depth: usize, mut visit: impl FnMut(&CubeState, &[Move]))
+{
+    let mut frontier = vec![(CubeState::solved(), Vec::new())];
+
+    for level in 0..=depth {
+        let mut next = Vec::new();
+
+        for (state, moves) in &frontier {
+            visit(state, moves);
+            if level == depth { continue; }
+
+            let last = moves.last().map(|m: &Move| m.layers);
+            for layers in layers.iter().filter(|layers| Some(**layers) != last) {
+                for turns in 1..4 {
+                    let m = Move::new(*layers, turns);
+                    next.push((state.apply_moves(&[m]), [moves.as_slice(), &[m]].concat()));
+                }
+            }
+        }
+
+        frontier = next;
+    }
+}
+
+fn parsed(algorithm: &str) -> Algorithm
+{
+    Algorithm::parse(algorithm).unwrap_or(Algorithm { nodes: Vec::new() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::{random_scramble, seeded_random};
+
+    #[test]
+    fn every_method_solves_the_cube()
+    {
+        let mut random = seeded_random(48);
+
+        for method in [BlindMethod::OldPochmann, BlindMethod::M2, BlindMethod::ThreeStyle] {
+            for _ in 0..40 {
+                let scramble = random_scramble(20, &mut random);
+                let state = CubeState::solved().apply_moves(&scramble);
+
+                let solution = BlindSolution::new(&state, method);
+                assert!(state.apply_moves(&solution.moves()).is_solved(false), "{:?} {}", method, format_moves(&scramble));
+            }
+        }
+    }
+
+    #[test]
+    fn every_target_can_be_swapped_with_the_buffer()
+    {
+        let swaps = [(op_corners(), PieceKind::Corner, CORNER_BUFFER), (op_edges(), PieceKind::Edge, EdgeMethod::OldPochmann.buffer()),
+            (m2_edges(), PieceKind::Edge, EdgeMethod::M2.buffer())];
+
+        for (swap, kind, buffer) in swaps {
+            let buffers: Vec<char> = turning(slot(buffer, kind)).into_iter().filter_map(letter).collect();
+
+            for target in ('A'..='X').filter(|target| !buffers.contains(target)) {
+                assert!(swap.setups.contains_key(&target) || swap.special.contains_key(&target), "{:?} {} from buffer {}", kind, target, buffer);
+            }
+        }
+    }
+
+    #[test]
+    fn solved_cube_needs_no_moves()
+    {
+        for method in [BlindMethod::OldPochmann, BlindMethod::M2, BlindMethod::ThreeStyle] {
+            assert!(BlindSolution::new(&CubeState::solved(), method).moves().is_empty());
+        }
+    }
+}
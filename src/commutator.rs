@@ -0,0 +1,91 @@
+
+use std::sync::OnceLock;
+
+use crate::notation::{Layers, Move, Node, Slice};
+use crate::permutation::PieceKind;
+use crate::state::{CubeState, Face};
+
+/*
+    Pure commutators [A B A', C] of single moves which cycle three corners or three edges and nothing else,
+    worked with as the slot each sticker comes from (see sources). Used for 3-style blindfolded solutions and FMC insertions
+*/
+
+// Pure commutator [A B A', C] of single moves, with the slot each sticker comes from when it is done from solved
+pub struct PureCommutator {
+    pub sources: [usize; 54],
+    insertion: Vec<Move>,
+    interchange: Move,
+}
+
+impl PureCommutator {
+
+    pub fn node(&self) -> Node
+    {
+        Node::Commutator(self.insertion.iter().map(|m| Node::Move(*m)).collect(), vec![Node::Move(self.interchange)], 1)
+    }
+}
+
+// Get the layers commutators for a kind of piece are made of (slices only move edges and centers, so are only used for edges)
+pub fn commutator_layers(kind: PieceKind) -> Vec<Layers>
+{
+    let faces = Face::ALL.map(Layers::Face);
+
+    match kind {
+        PieceKind::Corner => faces.to_vec(),
+        _ => faces.into_iter().chain([Slice::M, Slice::E, Slice::S].map(Layers::Slice)).collect(),
+    }
+}
+
+// Get every pure commutator which cycles three pieces of a kind and nothing else (each way of cycling them once), found the first time they are needed
+pub fn pure_commutators(kind: PieceKind) -> &'static [PureCommutator]
+{
+    static CORNERS: OnceLock<Vec<PureCommutator>> = OnceLock::new();
+    static EDGES: OnceLock<Vec<PureCommutator>> = OnceLock::new();
+
+    let table = if kind == PieceKind::Corner { &CORNERS } else { &EDGES };
+
+    table.get_or_init(|| {
+        let moves: Vec<Move> = commutator_layers(kind).iter().flat_map(|layers| (1..4).map(|turns| Move::new(*layers, turns))).collect();
+        let stickers = if kind == PieceKind::Corner { 9 } else { 6 };
+        let mut pure: Vec<PureCommutator> = Vec::new();
+
+        for a in &moves {
+            for b in moves.iter().filter(|b| b.layers != a.layers) {
+                for c in moves.iter().filter(|c| c.layers != a.layers && c.layers != b.layers) {
+
+                    let insertion = vec![*a, *b, a.inverse()];
+                    let inverse: Vec<Move> = insertion.iter().rev().map(|m| m.inverse()).collect();
+                    let sources = sources(&[&insertion[..], &[*c], &inverse, &[c.inverse()]].concat());
+
+                    let moved: Vec<usize> = (0..54).filter(|s| sources[*s] != *s).collect();
+                    let known = pure.iter().any(|known| known.sources == sources);
+                    if moved.len() == stickers && moved.iter().all(|s| is_kind(*s, kind)) && !known {
+                        pure.push(PureCommutator { sources, insertion, interchange: *c });
+                    }
+                }
+            }
+        }
+
+        pure
+    })
+}
+
+// Get the slot each sticker comes from when moves are done from solved
+pub fn sources(moves: &[Move]) -> [usize; 54]
+{
+    let state = CubeState::solved().apply_moves(moves);
+    std::array::from_fn(|s| state.sticker(s))
+}
+
+// Check a sticker slot is on a piece of a kind
+pub fn is_kind(slot: usize, kind: PieceKind) -> bool
+{
+    let corner = [0, 2, 6, 8].contains(&(slot % 9));
+    let edge = [1, 3, 5, 7].contains(&(slot % 9));
+
+    match kind {
+        PieceKind::Corner => corner,
+        PieceKind::Edge   => edge,
+        PieceKind::Center => !corner && !edge,
+    }
+}
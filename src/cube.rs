@@ -1,125 +1,99 @@
 
-use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject};
-use webgl_matrix::{Matrix, Mat4, Vec4};
+use webgl_matrix::{Matrix, Mat4};
 
-use std::{cell::RefCell, f32::consts};
+use crate::state::{cross, dot, Face, IVec3, Rotation};
+use crate::texture::AtlasLayout;
+
+//Sides of a cubie in the order they appear in its vertex arrays
+const SIDES: [Face; 6] = [Face::B, Face::F, Face::L, Face::R, Face::D, Face::U]; //back, front, left, right, bottom, top
+
+//Proportions of bevelled cubies (a cubie is 1.0 wide)
+const BEVEL_WIDTH: f32 = 0.08;      //How far back each edge of the cubie is cut
+const STICKER_HALF_WIDTH: f32 = 0.41;
+const STICKER_HEIGHT: f32 = 0.005;  //How far stickers sit above the sides of the cubie (stops z-fighting)
+
+//Number of floats of instance data per cubie (model matrix followed by the sprite sheet tile of each side)
+pub const INSTANCE_FLOATS: usize = 16 + 6;
+
+/*
+    Styles the cubies can be drawn in
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CubieStyle {
+    Flat,     // Unlit boxes with the stickers covering each side
+    Bevelled, // Lit boxes with bevelled edges and stickers inset on each side
+}
+
+// Vertex data shared by every cubie, with one array per vertex attribute
+#[derive(Default)]
+pub struct Geometry {
+    pub coordinates: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub faceCoords: Vec<f32>, // Position across the side of the cubie as seen from outside (between 0.0 and 1.0)
+    pub sides: Vec<f32>,      // Index into SIDES of the side the vertex belongs to
+    pub stickers: Vec<f32>,   // 1.0 if vertex belongs to a sticker, 0.0 if it belongs to the plastic body
+}
 
 pub struct Cube 
 {
-    VAO: WebGlVertexArrayObject,
-    // VBO: WebGlBuffer,
-    // IBO: WebGlBuffer,
-
-    position: Vec4, 
+    position: IVec3, 
     rotationMatrix: Mat4, 
-    numberOfVertices: i32,
+    tiles: [f32; 6], // Sprite sheet tile (row * columns + column) of each side, which depends on where the cubie started out
 }
 
 impl Cube {
 
-    pub fn new(gl: &WebGl2RenderingContext, shader_program: &WebGlProgram, position: Vec4, sideColourIndices: [u32; 6]) -> Self
+    pub fn new(home: IVec3, layout: AtlasLayout) -> Self
     {
-        //Create VAO for cube
-        let vao = gl.create_vertex_array().unwrap();
-
-        //Bind VAO to current webGL context
-        gl.bind_vertex_array(Some(&vao));
-
-        //Get coordinate, colour and texture arrays
-        let coordinates = Self::get_coordinates();
-        //let colours = Self::get_colours();                        
-        let textureCoords = Self::get_texture_coords(sideColourIndices);
-
-        // Note that `Float32Array::view` is somewhat dangerous (hence the `unsafe`!). This is creating a raw view into our
-        // module's `WebAssembly.Memory` buffer, but if we allocate more pages for ourself (aka do a memory allocation in Rust) 
-        // it'll cause the buffer to change, causing the `Float32Array` to be invalid. As a result, 
-        // after `Float32Array::view` we have to be very careful not to do any memory allocations before it's dropped.
-        
-        // COORDINATES
-        let coordinates_array = unsafe { js_sys::Float32Array::view(&coordinates) };
-        let coordinate_buffer = gl.create_buffer().unwrap();
-
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&coordinate_buffer));
-        gl.buffer_data_with_array_buffer_view( WebGl2RenderingContext::ARRAY_BUFFER, &coordinates_array, WebGl2RenderingContext::STATIC_DRAW);
-    
-        let coordinates_location = gl.get_attrib_location(&shader_program, "a_coords") as u32;
-        gl.vertex_attrib_pointer_with_i32( coordinates_location, 3, WebGl2RenderingContext::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(coordinates_location);
-
-        // TEXTURE COORDINATES
-        let textCoords_array = unsafe { js_sys::Float32Array::view(&textureCoords) };
-        let textCoords_buffer = gl.create_buffer().unwrap();
-
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&textCoords_buffer));
-        gl.buffer_data_with_array_buffer_view( WebGl2RenderingContext::ARRAY_BUFFER, &textCoords_array, WebGl2RenderingContext::STATIC_DRAW);
-   
-        let textCoords_location = gl.get_attrib_location(&shader_program, "a_textCoord") as u32;
-        gl.vertex_attrib_pointer_with_i32( textCoords_location, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(textCoords_location);
+        let (cols, _) = layout.size();
 
         Self {
-            VAO: vao,
-            position: position,
+            position: home,
             rotationMatrix: Mat4::identity(),
-            numberOfVertices: 108 / 3, // == 36, since 6 vertices per side & 6 sides
+            tiles: SIDES.map(|side| { let (col, row) = layout.tile(home, side); (row * cols + col) as f32 }),
         }
-
     }
 
-    // Method to swap the pointers of cubies rotation matrices (since cubies are rotated in place and not translated)
-    pub fn swapCubesRotationMatrices(&mut self, other: &mut Cube)
+    // Method to get the current position of the cubie within the rubik's cube
+    pub fn position(&self) -> IVec3
     {
-        use std::mem;
-
-        //Swap pointers of 2 cubies rotation matrices
-        mem::swap(&mut self.rotationMatrix, &mut other.rotationMatrix); 
+        self.position
     }
 
-    // Method to rotate a cubies matrix along a given axis //Note: angle is always PI/2 since this is final step
-    pub fn setCubeRotationMatrix(&mut self, axis: &[f32; 3])
+    // Method to move cubie to a position within the rubik's cube and rotate it (as given by the logical state)
+    pub fn setCubeTransform(&mut self, position: IVec3, rotation: &Rotation)
     {
-        // Multiply axis by matrix to get the axis along which we want to rotate
-        self.rotationMatrix.rotate(consts::PI / 2.0, &self.rotationMatrix.mul_vector(axis) );
+        self.position = position;
+
+        //NOTE: webGL matrices are column major
+        self.rotationMatrix = Mat4::identity();
+        for (r, row) in rotation.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                self.rotationMatrix[c * 4 + r] = *value as f32;
+            }
+        }
     }
 
-    //Function for object to render itself
-    pub fn draw(&self, 
-            gl_refcell: &RefCell<WebGl2RenderingContext>,
-            shader_program: &WebGlProgram,
-            model_view_matrix: &mut [f32; 16], 
-            model_view_matrix_location: &WebGlUniformLocation,
-        )
-        {//NOTE: webGL matrices are column major, printed as row major
-
-            //Set position for cubie within rubix cube
-            model_view_matrix.translate(&self.position);
-
-            //Clone to avoid mutating rotation matrix when matrix multiplying later (which modifies left param)
-            let mut klone = self.rotationMatrix.clone();
-
-            //Deref ptr to matrix multiplication and put into model matrix ptr
-            *model_view_matrix = *klone.mul(&model_view_matrix); 
-
-            //Convert model matrix to vector
-            let vec_model_view_matrix = model_view_matrix.iter().map(|v| *v).collect::<Vec<_>>();
-        
-            //Bind shader to current webGL context
-            gl_refcell.borrow().use_program(Some(&shader_program));
-
-            //Set model_view matrix uniform for currently bound shader program
-            gl_refcell.borrow().uniform_matrix4fv_with_f32_array(Some(model_view_matrix_location),false,&vec_model_view_matrix);
-        
-            //Bind this cube's VAO to current webGL context
-            gl_refcell.borrow().bind_vertex_array(Some(&self.VAO));
-
-            //Make draw call finally                                                        
-            gl_refcell.borrow().draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, self.numberOfVertices); 
-        
+    //Function for object to add its per instance data (ie. model matrix and tiles) to the data drawn this frame
+    pub fn push_instance_data(&self, model_view_matrix: &mut [f32; 16], instanceData: &mut Vec<f32>)
+    {//NOTE: webGL matrices are column major, printed as row major
+
+        //Set position for cubie within rubix cube
+        model_view_matrix.translate(&self.position.map(|x| x as f32));
+
+        //Clone to avoid mutating rotation matrix when matrix multiplying later (which modifies left param)
+        let mut klone = self.rotationMatrix;
+
+        //Deref ptr to matrix multiplication and put into model matrix ptr
+        *model_view_matrix = *klone.mul(model_view_matrix); 
+
+        instanceData.extend_from_slice(model_view_matrix);
+        instanceData.extend_from_slice(&self.tiles);
     }
 
     fn get_coordinates() -> [f32; 108]
     {
-        let coordinates = [  //back, front, left, right, bottom, top
+        [  //back, front, left, right, bottom, top
             -0.5, -0.5, -0.5,        
              0.5, -0.5, -0.5,        
              0.5,  0.5, -0.5,        
@@ -161,73 +135,118 @@ impl Cube {
              0.5,  0.5,  0.5,        
             -0.5,  0.5,  0.5,        
             -0.5,  0.5, -0.5,        
-        ];
-    
-        coordinates
+        ]
     }
     
 
-    fn get_texture_coords(indices: [u32; 6]) -> [f32; 72] // indices for sprite sheet
+    // Get vertex data for a plain box with a sticker covering each whole side
+    pub fn get_flat_geometry() -> Geometry
+    {
+        let coordinates = Self::get_coordinates();
+        let mut geometry = Geometry { coordinates: coordinates.to_vec(), ..Default::default() };
+
+        //Loop to set where on its side each vertex is, so the shader can find it in the sprite sheet
+        for (i, vertex) in coordinates.chunks(3).enumerate() {
+
+            let side = SIDES[i / 6]; // 6 vertices per side
+
+            //Position of vertex across the side as seen from outside the cubie (between 0.0 and 1.0)
+            let u = (0..3).map(|k| vertex[k] * side.right()[k] as f32).sum::<f32>() + 0.5;
+            let v = (0..3).map(|k| vertex[k] * side.up()[k] as f32).sum::<f32>() + 0.5;
+
+            geometry.normals.extend(side.normal().map(|x| x as f32));
+            geometry.faceCoords.extend([u, v]);
+            geometry.sides.push((i / 6) as f32);
+            geometry.stickers.push(1.0);
+        }
+
+        geometry
+    }
+
+    // Get vertex data for a box with bevelled edges and corners, with a slightly raised sticker on each side
+    pub fn get_bevelled_geometry() -> Geometry
+    {
+        let mut geometry = Geometry::default();
+        let inner = 0.5 - BEVEL_WIDTH;
+
+        for (i, side) in SIDES.into_iter().enumerate() {
+
+            //Point on the side, given how far right, up and out from the center of the cubie it is
+            let point = |right: f32, up: f32, out: f32| combine(&[(right, side.right()), (up, side.up()), (out, side.normal())]);
+            let normal = side.normal().map(|x| x as f32);
+
+            //Flat middle of side
+            let body = [point(-inner, -inner, 0.5), point(inner, -inner, 0.5), point(inner, inner, 0.5), point(-inner, inner, 0.5)];
+            geometry.push_quad(body, normal, None);
+
+            //Sticker sitting on top of side
+            let (w, h) = (STICKER_HALF_WIDTH, 0.5 + STICKER_HEIGHT);
+            let sticker = [point(-w, -w, h), point(w, -w, h), point(w, w, h), point(-w, w, h)];
+            geometry.push_quad(sticker, normal, Some(i));
+
+            //Bevel along each edge shared with a later side (so each of the 12 edges is made once)
+            for other in SIDES.iter().skip(i).filter(|other| dot(side.normal(), other.normal()) == 0) {
+
+                let along = cross(side.normal(), other.normal());
+                let edge = [
+                    combine(&[(0.5, side.normal()), (inner, other.normal()), (-inner, along)]),
+                    combine(&[(0.5, side.normal()), (inner, other.normal()), ( inner, along)]),
+                    combine(&[(inner, side.normal()), (0.5, other.normal()), ( inner, along)]),
+                    combine(&[(inner, side.normal()), (0.5, other.normal()), (-inner, along)]),
+                ];
+                geometry.push_quad(edge, normalise(combine(&[(1.0, side.normal()), (1.0, other.normal())])), None);
+            }
+        }
+
+        //Triangle cutting off each of the 8 corners
+        for corner in 0..8 {
+
+            let signs = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1].map(|bit| if bit == 1 { 1.0 } else { -1.0 });
+            let triangle: [[f32; 3]; 3] = std::array::from_fn(|axis| std::array::from_fn(|k| signs[k] * if k == axis { 0.5 } else { inner }));
+
+            for vertex in triangle {
+                geometry.push_vertex(vertex, normalise(signs), [0.0, 0.0], 0.0, 0.0);
+            }
+        }
+
+        geometry
+    }
+
+}
+
+impl Geometry {
+
+    fn push_vertex(&mut self, coordinate: [f32; 3], normal: [f32; 3], faceCoord: [f32; 2], side: f32, sticker: f32)
     {
-        let mut textCoords = [
-            0.0, 0.0,
-            1.0, 0.0,
-            1.0, 1.0,
-            1.0, 1.0,
-            0.0, 1.0,
-            0.0, 0.0,
-            
-            0.0, 0.0,
-            1.0, 0.0,
-            1.0, 1.0,
-            1.0, 1.0,
-            0.0, 1.0,
-            0.0, 0.0,
-            
-            1.0, 0.0,
-            1.0, 1.0,
-            0.0, 1.0,
-            0.0, 1.0,
-            0.0, 0.0,
-            1.0, 0.0,
-            
-            1.0, 0.0,
-            1.0, 1.0,
-            0.0, 1.0,
-            0.0, 1.0,
-            0.0, 0.0,
-            1.0, 0.0,
-            
-            0.0, 1.0,
-            1.0, 1.0,
-            1.0, 0.0,
-            1.0, 0.0,
-            0.0, 0.0,
-            0.0, 1.0,
-            
-            0.0, 1.0,
-            1.0, 1.0,
-            1.0, 0.0,
-            1.0, 0.0,
-            0.0, 0.0,
-            0.0, 1.0,
-        ];
-
-        let cols = indices.len();
-
-        //Loop to adjust texture coordinate array to correctly access sprite sheet
-        for (i, coord) in textCoords.into_iter().enumerate() {
-            
-            //vec2((v_textCoord.x + spriteIndex.x) * SPRITE_COLS, (v_textCoord.y + spriteIndex.y) * SPRITE_ROWS)
-            if i % 2 == 0 {
-                textCoords[i] = (coord + (indices[(i/(2*cols)) % cols] as f32)) * (1.0 / cols as f32);
-            }else{
-                textCoords[i] = coord as f32;
-            } 
+        self.coordinates.extend(coordinate);
+        self.normals.extend(normal);
+        self.faceCoords.extend(faceCoord);
+        self.sides.push(side);
+        self.stickers.push(sticker);
+    }
+
+    // Add a quad (as 2 triangles) given its corners in order. Quads not on a side (ie. sticker) are plastic
+    fn push_quad(&mut self, corners: [[f32; 3]; 4], normal: [f32; 3], side: Option<usize>)
+    {
+        let faceCoords = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+
+        for i in [0, 1, 2, 2, 3, 0] {
+            match side {
+                Some(side) => self.push_vertex(corners[i], normal, faceCoords[i], side as f32, 1.0),
+                None       => self.push_vertex(corners[i], normal, [0.0, 0.0], 0.0, 0.0),
+            }
         }
-        
-        textCoords
     }
+}
 
+// Sum of integer directions scaled by their weights
+fn combine(terms: &[(f32, IVec3)]) -> [f32; 3]
+{
+    std::array::from_fn(|k| terms.iter().map(|(weight, direction)| weight * direction[k] as f32).sum())
 }
 
+fn normalise(v: [f32; 3]) -> [f32; 3]
+{
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    v.map(|x| x / length)
+}
@@ -0,0 +1,69 @@
+
+use std::fmt;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/*
+    Everything that can go wrong while setting up the simulation. Thrown to JS as an Error named "RubixError"
+*/
+#[derive(Debug)]
+pub enum RubixError {
+    NoDocument,                                              // Not running in a page (eg. inside a web worker)
+    MissingElement(String),                                  // No element in the document with this id
+    WrongElementType { id: String, expected: &'static str }, // Element exists but is not the kind expected
+    WebGl2Unsupported,                                       // Browser (or canvas) cannot give a WebGL2 context
+    GlObject(&'static str),                                  // WebGL refused to create an object (usually as the context is lost)
+    ShaderCompile { stage: &'static str, log: String },      // Info log of the shader which failed to compile
+    ShaderLink(String),                                      // Info log of the program which failed to link
+    Texture(String),                                         // Bad sticker colours or images for the texture atlas
+    InvalidOption(String),                                   // Option given to a CubeApp is the wrong type
+    InvalidAlgorithm(String),                                // Algorithm which isn't valid cube notation
+    InvalidState(String),                                    // Facelet string which isn't a possible cube
+    Js(String),                                              // Exception thrown by a browser API
+}
+
+impl fmt::Display for RubixError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            RubixError::NoDocument                     => write!(f, "No document to run in (the simulation must run in a web page)"),
+            RubixError::MissingElement(id)             => write!(f, "No element with id \"{}\" in the document", id),
+            RubixError::WrongElementType { id, expected } => write!(f, "Element with id \"{}\" is not a {}", id, expected),
+            RubixError::WebGl2Unsupported              => write!(f, "WebGL2 is not supported by this browser"),
+            RubixError::GlObject(object)               => write!(f, "Unable to create WebGL {}", object),
+            RubixError::ShaderCompile { stage, log }   => write!(f, "Unable to compile {} shader: {}", stage, log),
+            RubixError::ShaderLink(log)                => write!(f, "Unable to link shader program: {}", log),
+            RubixError::Texture(message)               => write!(f, "Unable to load texture: {}", message),
+            RubixError::InvalidOption(message)         => write!(f, "Invalid option: {}", message),
+            RubixError::InvalidAlgorithm(message)      => write!(f, "Invalid algorithm: {}", message),
+            RubixError::InvalidState(message)          => write!(f, "Invalid cube state: {}", message),
+            RubixError::Js(message)                    => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for RubixError {}
+
+// Exceptions thrown by web_sys calls keep their message
+impl From<JsValue> for RubixError {
+
+    fn from(value: JsValue) -> Self
+    {
+        let message = match value.dyn_ref::<js_sys::Error>() {
+            Some(error) => String::from(error.message()),
+            None        => value.as_string().unwrap_or_else(|| format!("{:?}", value)),
+        };
+        RubixError::Js(message)
+    }
+}
+
+impl From<RubixError> for JsValue {
+
+    fn from(error: RubixError) -> Self
+    {
+        let exception = js_sys::Error::new(&error.to_string());
+        exception.set_name("RubixError");
+        exception.into()
+    }
+}
@@ -0,0 +1,160 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::RubixError;
+use crate::notation::{format_moves, Move};
+use crate::progress::CrossProgress;
+use crate::trainer::TrainerResult;
+
+#[wasm_bindgen(typescript_custom_section)]
+const CUBE_EVENTS: &'static str = r#"
+/** Events sent to callbacks subscribed with CubeApp.on. Moves are written in standard notation (eg. "R'") */
+export type CubeEvent =
+    | { type: "moveStarted"; move: string }
+    | { type: "moveFinished"; move: string }
+    | { type: "solved" }
+    | { type: "scrambled"; scramble: string }
+    | { type: "viewChanged"; yaw: number; pitch: number }
+    | { type: "caseTrained"; card: TrainerCard; solved: boolean; recognitionTime: number; executionTime: number }
+    | { type: "progress"; progress: CrossProgress[] }
+    | { type: "fmcTimeUp" };
+
+export type CubeEventType = CubeEvent["type"];
+
+export type CubeEventCallback = (event: CubeEvent) => void;
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CubeEventType")]
+    pub type CubeEventType;
+
+    #[wasm_bindgen(typescript_type = "CubeEventCallback")]
+    pub type CubeEventCallback;
+}
+
+/*
+    Things happening to a rubik's cube which JS can subscribe to
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum CubeEvent {
+    MoveStarted(Move),                    // Animation of a move has begun
+    MoveFinished(Move),                   // Move has been applied to the cube
+    Solved,                               // Move finished with the cube solved
+    Scrambled(Vec<Move>),                 // Scramble was applied (instantly) to the cube
+    ViewChanged { yaw: f32, pitch: f32 }, // Whole cube was turned to be seen from a new angle (in radians)
+    CaseTrained(TrainerResult),           // Trainer case was solved (or skipped) and rescheduled
+    Progress(Vec<CrossProgress>),         // Cross and F2L progress for every cross colour, after each move
+    FmcTimeUp,                            // Hour allowed for an FMC attempt ran out
+}
+
+impl CubeEvent {
+
+    pub const TYPES: [&'static str; 8] = ["moveStarted", "moveFinished", "solved", "scrambled", "viewChanged", "caseTrained", "progress", "fmcTimeUp"];
+
+    pub fn event_type(&self) -> &'static str
+    {
+        match self {
+            CubeEvent::MoveStarted(_)      => "moveStarted",
+            CubeEvent::MoveFinished(_)     => "moveFinished",
+            CubeEvent::Solved              => "solved",
+            CubeEvent::Scrambled(_)        => "scrambled",
+            CubeEvent::ViewChanged { .. }  => "viewChanged",
+            CubeEvent::CaseTrained(_)      => "caseTrained",
+            CubeEvent::Progress(_)         => "progress",
+            CubeEvent::FmcTimeUp           => "fmcTimeUp",
+        }
+    }
+
+    // Method to build the plain JS object passed to callbacks
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("type", JsValue::from_str(self.event_type()));
+
+        match self {
+            CubeEvent::MoveStarted(m) | CubeEvent::MoveFinished(m) => set("move", JsValue::from_str(&m.to_string())),
+            CubeEvent::Scrambled(moves) => set("scramble", JsValue::from_str(&format_moves(moves))),
+            CubeEvent::ViewChanged { yaw, pitch } => {
+                set("yaw", JsValue::from_f64(*yaw as f64));
+                set("pitch", JsValue::from_f64(*pitch as f64));
+            },
+            CubeEvent::CaseTrained(result) => {
+                set("card", result.card.to_js());
+                set("solved", JsValue::from_bool(result.solved));
+                set("recognitionTime", JsValue::from_f64(result.recognitionTime));
+                set("executionTime", JsValue::from_f64(result.executionTime));
+            },
+            CubeEvent::Progress(progress) => set("progress", progress.iter().map(|colour| colour.to_js()).collect::<js_sys::Array>().into()),
+            CubeEvent::Solved | CubeEvent::FmcTimeUp => (),
+        }
+
+        object.into()
+    }
+}
+
+/*
+    JS callbacks subscribed to the events of a cube, each with an id so it can be unsubscribed
+*/
+#[derive(Default)]
+pub struct Subscribers {
+    nextId: u32,
+    callbacks: Vec<(u32, String, js_sys::Function)>,
+    waiters: Vec<(u64, js_sys::Function)>, // Resolve functions of promises waiting for a count of moves to finish
+}
+
+impl Subscribers {
+
+    pub fn subscribe(&mut self, eventType: &str, callback: js_sys::Function) -> Result<u32, RubixError>
+    {
+        if !CubeEvent::TYPES.contains(&eventType) {
+            return Err(RubixError::InvalidOption(format!("Unknown event type \"{}\", expected one of {}", eventType, CubeEvent::TYPES.join(", "))));
+        }
+
+        self.nextId += 1;
+        self.callbacks.push((self.nextId, eventType.to_string(), callback));
+        Ok(self.nextId)
+    }
+
+    // Returns false if there was no subscription with the id
+    pub fn unsubscribe(&mut self, id: u32) -> bool
+    {
+        let count = self.callbacks.len();
+        self.callbacks.retain(|(callbackId, _, _)| *callbackId != id);
+        self.callbacks.len() != count
+    }
+
+    // Method to wait for the given count of moves to have finished
+    pub fn wait(&mut self, movesQueued: u64, resolve: js_sys::Function)
+    {
+        self.waiters.push((movesQueued, resolve));
+    }
+
+    // Take the resolve functions of everything waiting on moves which have now finished
+    pub fn take_finished(&mut self, movesFinished: u64) -> Vec<js_sys::Function>
+    {
+        let (finished, waiting) = std::mem::take(&mut self.waiters).into_iter()
+            .partition::<Vec<_>, _>(|(movesQueued, _)| *movesQueued <= movesFinished);
+
+        self.waiters = waiting;
+        finished.into_iter().map(|(_, resolve)| resolve).collect()
+    }
+
+    // Unsubscribes every callback, returning the resolve functions of anything still waiting (so they aren't left hanging)
+    pub fn clear(&mut self) -> Vec<js_sys::Function>
+    {
+        self.callbacks.clear();
+        self.waiters.drain(..).map(|(_, resolve)| resolve).collect()
+    }
+
+    // Get the callbacks subscribed to an event (cloned so callbacks are free to subscribe and unsubscribe while being called)
+    pub fn callbacks_for(&self, event: &CubeEvent) -> Vec<js_sys::Function>
+    {
+        self.callbacks.iter()
+            .filter(|(_, eventType, _)| eventType == event.event_type())
+            .map(|(_, _, callback)| callback.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,451 @@
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use wasm_bindgen::prelude::*;
+
+use crate::commutator::{commutator_layers, is_kind, pure_commutators, sources};
+use crate::error::RubixError;
+use crate::history::History;
+use crate::metrics::Metrics;
+use crate::notation::{format_moves, parse_moves, Algorithm, Move, Node};
+use crate::permutation::PieceKind;
+use crate::simplify::simplify;
+use crate::state::{slot_position, CubeState};
+
+/*
+    Fewest moves (FMC) practice: skeletons written with the moves done on the inverse scramble (NISS) in brackets, an insertion
+    finder which puts commutators into a skeleton to solve the 3-cycles it leaves, and the 60 minute time limit.
+    With NISS, moves on the inverse scramble count as premoves on the normal one (and the other way round), so the solution
+    is the normal moves followed by the inverse moves undone. eg. "R U (F' D)" is solved by R U D' F
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const FMC: &'static str = r#"
+/** Commutator inserted after a number of the skeleton's moves, with how many moves cancelled with the moves around it */
+export interface Insertion {
+    position: number;
+    algorithm: string;
+    cancelled: number;
+}
+
+/** Insertions which solve the 3-cycles a skeleton leaves, one after another, along with the solution they give */
+export interface InsertionResult {
+    insertions: Insertion[];
+    solution: string;
+    moveCount: number;
+    solved: boolean;
+}
+
+/** Where an FMC attempt is up to. The skeleton has the moves done on the inverse scramble in brackets */
+export interface FmcStatus {
+    scramble: string;
+    skeleton: string;
+    solution: string;
+    moveCount: number;
+    inverse: boolean;
+    timeLeft: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "InsertionResult")]
+    pub type InsertionResult;
+
+    #[wasm_bindgen(typescript_type = "FmcStatus | undefined")]
+    pub type FmcStatus;
+}
+
+// Time allowed for an FMC attempt (in seconds)
+pub const FMC_TIME_LIMIT: f64 = 60.0 * 60.0;
+
+/*
+ * Find commutators to insert into a skeleton (which may have moves on the inverse in brackets) to solve the corner and edge
+ * 3-cycles it leaves after the scramble. Throws a RubixError if either is invalid
+ */
+#[wasm_bindgen(js_name = findInsertions)]
+pub fn findInsertions(scramble: &str, skeleton: &str) -> Result<InsertionResult, JsValue>
+{
+    let scramble = parse_moves(scramble)?;
+    let skeleton = Skeleton::parse(skeleton)?;
+
+    Ok(Insertions::find(&CubeState::solved().apply_moves(&scramble), &skeleton.solution()).to_js().unchecked_into())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Skeleton {
+    pub normal: Vec<Move>,  // Moves done on the scramble
+    pub inverse: Vec<Move>, // Moves done on the inverse scramble
+}
+
+impl Skeleton {
+
+    // Read a skeleton where moves in brackets are on the inverse scramble eg. "R U (F' D) L2"
+    pub fn parse(skeleton: &str) -> Result<Self, RubixError>
+    {
+        let mut parsed = Self::default();
+        let mut rest = skeleton;
+
+        while !rest.is_empty() {
+            let (normal, inverse) = match rest.split_once('(') {
+                Some((normal, inverse)) => (normal, Some(inverse)),
+                None                    => (rest, None),
+            };
+            parsed.normal.extend(Algorithm::parse(normal)?.expand());
+
+            let Some(inverse) = inverse else { break; };
+            let (inverse, after) = inverse.split_once(')')
+                .ok_or_else(|| RubixError::InvalidAlgorithm(format!("Unclosed bracket in \"{}\"", skeleton)))?;
+
+            if inverse.contains('(') {
+                return Err(RubixError::InvalidAlgorithm(format!("Brackets can't be nested in a skeleton, got \"{}\"", skeleton)));
+            }
+            parsed.inverse.extend(Algorithm::parse(inverse)?.expand());
+            rest = after;
+        }
+
+        Ok(parsed)
+    }
+
+    // Get the moves which solve the scramble: the normal moves then the inverse moves undone
+    pub fn solution(&self) -> Vec<Move>
+    {
+        self.normal.iter().copied().chain(self.inverse.iter().rev().map(|m| m.inverse())).collect()
+    }
+
+    /*
+        Get the state on either side, from the state before the scramble. Moves on the other side come first as premoves:
+        the normal side is the inverse moves undone then the scramble and normal moves, the inverse side is the normal moves undone
+        then the scramble undone and the inverse moves
+    */
+    pub fn state(&self, unscrambled: &CubeState, scramble: &[Move], inverse: bool) -> CubeState
+    {
+        let undo = |moves: &[Move]| moves.iter().rev().map(|m| m.inverse()).collect::<Vec<_>>();
+
+        let moves = match inverse {
+            false => [undo(&self.inverse), scramble.to_vec(), self.normal.clone()].concat(),
+            true  => [undo(&self.normal), undo(scramble), self.inverse.clone()].concat(),
+        };
+
+        unscrambled.apply_moves(&moves)
+    }
+}
+
+impl std::fmt::Display for Skeleton {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match (self.normal.is_empty(), self.inverse.is_empty()) {
+            (_, true)     => write!(f, "{}", format_moves(&self.normal)),
+            (true, false) => write!(f, "({})", format_moves(&self.inverse)),
+            (false, false) => write!(f, "{} ({})", format_moves(&self.normal), format_moves(&self.inverse)),
+        }
+    }
+}
+
+// Time limit of an FMC attempt, which only runs out once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FmcTimer {
+    pub deadline: f64, // Time (in seconds, see get_current_time) the attempt ends at
+    expired: bool,
+}
+
+impl FmcTimer {
+
+    pub fn new(now: f64) -> Self
+    {
+        Self { deadline: now + FMC_TIME_LIMIT, expired: false }
+    }
+
+    pub fn time_left(&self, now: f64) -> f64
+    {
+        (self.deadline - now).max(0.0)
+    }
+
+    // Method to check if the time has just run out, which is only true the first time it is checked after the deadline
+    pub fn expire(&mut self, now: f64) -> bool
+    {
+        let expiring = !self.expired && now >= self.deadline;
+        self.expired |= expiring;
+        expiring
+    }
+}
+
+// Build the plain JS object describing an FMC attempt from the moves made since it was scrambled (see FmcStatus)
+pub fn fmc_status(history: &History, timer: &FmcTimer, now: f64) -> JsValue
+{
+    let solution = simplify(&history.solution());
+
+    let object = js_sys::Object::new();
+    let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+    set("scramble", JsValue::from_str(&format_moves(&history.scramble)));
+    set("skeleton", JsValue::from_str(&history.skeleton().to_string()));
+    set("solution", JsValue::from_str(&format_moves(&solution)));
+    set("moveCount", JsValue::from(Metrics::of(&solution).htm));
+    set("inverse", JsValue::from_bool(history.inverse));
+    set("timeLeft", JsValue::from_f64(timer.time_left(now)));
+
+    object.into()
+}
+
+// Commutator inserted into a skeleton, after a number of its moves
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Insertion {
+    pub position: usize,
+    pub algorithm: Algorithm,
+    pub cancelled: usize, // Moves cancelled with the skeleton's moves either side
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Insertions {
+    pub insertions: Vec<Insertion>,
+    pub solution: Vec<Move>,
+    pub solved: bool,
+}
+
+impl Insertions {
+
+    /*
+        Insert commutators into a skeleton one at a time, each solving one of the corner or edge 3-cycles the skeleton leaves
+        from the scrambled state, choosing the insertion which leaves the fewest moves (after cancelling). Stops once the cube is solved
+        or nothing left can be solved by inserting a 3-cycle
+    */
+    pub fn find(scrambled: &CubeState, skeleton: &[Move]) -> Self
+    {
+        let mut found = Self { insertions: Vec::new(), solution: skeleton.to_vec(), solved: false };
+
+        loop {
+            let state = scrambled.apply_moves(&found.solution);
+            let left: [usize; 54] = std::array::from_fn(|slot| state.sticker(slot));
+            found.solved = (0..54).all(|slot| left[slot] == slot);
+
+            let cycles = three_cycles(&left);
+            if found.solved || cycles.is_empty() { return found; }
+
+            let mut best: Option<(Vec<Move>, Insertion)> = None;
+
+            for position in 0..=found.solution.len() {
+                let after = sources(&found.solution[position..]);
+                let mut undoAfter = [0; 54];
+                for slot in 0..54 { undoAfter[after[slot]] = slot; }
+
+                for cycle in &cycles {
+                    //Doing a commutator here then the rest of the skeleton undoes the cycle when it is the cycle undone, set up by the moves after
+                    let mut undo = [0; 54];
+                    for slot in 0..54 { undo[cycle[slot]] = slot; }
+                    let needed: [u8; 54] = std::array::from_fn(|slot| after[undo[undoAfter[slot]]] as u8);
+
+                    let Some(algorithm) = insertion_table().get(&needed) else { continue; };
+                    let inserted = algorithm.expand();
+                    let solution = simplify(&[&found.solution[..position], &inserted, &found.solution[position..]].concat());
+
+                    if best.as_ref().is_none_or(|(shortest, _)| solution.len() < shortest.len()) {
+                        let cancelled = found.solution.len() + inserted.len() - solution.len();
+                        best = Some((solution, Insertion { position, algorithm: algorithm.clone(), cancelled }));
+                    }
+                }
+            }
+
+            let Some((solution, insertion)) = best else { return found; };
+            found.solution = solution;
+            found.insertions.push(insertion);
+        }
+    }
+
+    // Method to build the plain JS object given to JS (see InsertionResult)
+    pub fn to_js(&self) -> JsValue
+    {
+        let insertions = self.insertions.iter().map(|insertion| {
+            let object = js_sys::Object::new();
+            let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+            set("position", JsValue::from(insertion.position as u32));
+            set("algorithm", JsValue::from_str(&insertion.algorithm.to_string()));
+            set("cancelled", JsValue::from(insertion.cancelled as u32));
+            object
+        }).collect::<js_sys::Array>();
+
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("insertions", insertions.into());
+        set("solution", JsValue::from_str(&format_moves(&self.solution)));
+        set("moveCount", JsValue::from(Metrics::of(&self.solution).htm));
+        set("solved", JsValue::from_bool(self.solved));
+
+        object.into()
+    }
+}
+
+// Get the 3-cycles of pieces (with no twist) left in a state, given as the slot each sticker comes from, each on its own
+fn three_cycles(left: &[usize; 54]) -> Vec<[usize; 54]>
+{
+    let piece = |slot: usize| slot_position(slot).0;
+    let mut cycles: Vec<[usize; 54]> = Vec::new();
+
+    for start in (0..54).filter(|slot| left[*slot] != *slot && !is_kind(*slot, PieceKind::Center)) {
+        if cycles.iter().any(|cycle| cycle[start] != start) { continue; }
+
+        //Stickers of an untwisted 3-cycle go round in threes, each time to another piece
+        let second = left[start];
+        let third = left[second];
+        if left[third] != start || piece(start) == piece(second) || piece(second) == piece(third) || piece(third) == piece(start) { continue; }
+
+        let pieces = [piece(start), piece(second), piece(third)];
+        let cycle: [usize; 54] = std::array::from_fn(|slot| if pieces.contains(&piece(slot)) { left[slot] } else { slot });
+
+        //Every sticker of the three pieces has to stay within them
+        if (0..54).all(|slot| pieces.contains(&piece(slot)) == pieces.contains(&piece(cycle[slot]))) {
+            cycles.push(cycle);
+        }
+    }
+
+    cycles
+}
+
+/*
+    Commutators to insert by what they do (the slot each sticker comes from): every pure commutator of single moves
+    (see pure_commutators), and each of those set up by one move. The shortest is kept for each 3-cycle
+*/
+fn insertion_table() -> &'static HashMap<[u8; 54], Algorithm>
+{
+    static TABLE: OnceLock<HashMap<[u8; 54], Algorithm>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        let mut table = HashMap::new();
+
+        for kind in [PieceKind::Corner, PieceKind::Edge] {
+            let commutators = pure_commutators(kind);
+
+            for commutator in commutators {
+                table.entry(commutator.sources.map(|slot| slot as u8)).or_insert(Algorithm { nodes: vec![commutator.node()] });
+            }
+
+            let setups = commutator_layers(kind).into_iter().flat_map(|layers| (1..4).map(move |turns| Move::new(layers, turns)));
+            for setup in setups {
+                let setupSources = sources(&[setup]);
+                let mut undo = [0; 54];
+                for slot in 0..54 { undo[setupSources[slot]] = slot; }
+
+                for commutator in commutators {
+                    let cycle = std::array::from_fn(|slot| setupSources[commutator.sources[undo[slot]]] as u8);
+                    table.entry(cycle).or_insert_with(|| Algorithm { nodes: vec![Node::Conjugate(vec![Node::Move(setup)], vec![commutator.node()], 1)] });
+                }
+            }
+        }
+
+        table
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::{random_scramble, seeded_random};
+
+    fn undo(moves: &[Move]) -> Vec<Move>
+    {
+        moves.iter().rev().map(|m| m.inverse()).collect()
+    }
+
+    #[test]
+    fn skeletons_parse_with_inverse_moves_in_brackets()
+    {
+        let skeleton = Skeleton::parse("R U (F' D) L2").unwrap();
+
+        assert_eq!(skeleton.normal, parse_moves("R U L2").unwrap());
+        assert_eq!(skeleton.inverse, parse_moves("F' D").unwrap());
+        assert_eq!(skeleton.solution(), parse_moves("R U L2 D' F").unwrap());
+        assert_eq!(skeleton.to_string(), "R U L2 (F' D)");
+
+        assert_eq!(Skeleton::parse("(R U) (F2)").unwrap().to_string(), "(R U F2)");
+        assert_eq!(Skeleton::parse("[R, U] (x)").unwrap().normal, parse_moves("R U R' U'").unwrap());
+        assert_eq!(Skeleton::parse("").unwrap(), Skeleton::default());
+
+        for invalid in ["R (U", "R (U (F) D)", "R (Q)", "R) U"] {
+            assert!(Skeleton::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn either_side_is_the_other_undone()
+    {
+        let mut random = seeded_random(50);
+        let unscrambled = CubeState::solved();
+
+        for _ in 0..20 {
+            let scramble = random_scramble(15, &mut random);
+            let skeleton = Skeleton { normal: random_scramble(6, &mut random), inverse: random_scramble(6, &mut random) };
+
+            let normal = skeleton.state(&unscrambled, &scramble, false);
+            let inverse = skeleton.state(&unscrambled, &scramble, true);
+            assert!((0..54).all(|slot| normal.sticker(inverse.sticker(slot)) == slot));
+
+            //Both sides are solved once the solution solves the scramble
+            let scramble = undo(&skeleton.solution());
+            assert!(skeleton.state(&unscrambled, &scramble, false).is_solved(false));
+            assert!(skeleton.state(&unscrambled, &scramble, true).is_solved(false));
+        }
+    }
+
+    #[test]
+    fn insertions_solve_the_cycles_a_skeleton_leaves()
+    {
+        let mut random = seeded_random(51);
+
+        for _ in 0..10 {
+            //A solution with a corner and an edge commutator in it, scrambled by undoing it, leaves both cycles without them
+            let skeleton = random_scramble(10, &mut random);
+
+            let inserted: Vec<(usize, Vec<Move>)> = [PieceKind::Edge, PieceKind::Corner].into_iter().map(|kind| {
+                let commutators = pure_commutators(kind);
+                let commutator = &commutators[(random() * commutators.len() as f64) as usize % commutators.len()];
+                ((random() * (skeleton.len() + 1) as f64) as usize, Algorithm { nodes: vec![commutator.node()] }.expand())
+            }).collect();
+
+            let mut full = Vec::new();
+            for position in 0..=skeleton.len() {
+                for (_, commutator) in inserted.iter().filter(|(at, _)| *at == position) { full.extend(commutator); }
+                full.extend(skeleton.get(position));
+            }
+
+            let scrambled = CubeState::solved().apply_moves(&undo(&full));
+            let found = Insertions::find(&scrambled, &skeleton);
+
+            assert!(found.solved, "{} leaves {}", format_moves(&full), format_moves(&found.solution));
+            assert_eq!(found.insertions.len(), 2);
+            assert!(scrambled.apply_moves(&found.solution).is_solved(false));
+            assert!(found.solution.len() <= skeleton.len() + 2 * 10);
+        }
+    }
+
+    #[test]
+    fn only_three_cycles_are_inserted()
+    {
+        let skeleton = parse_moves("R U F").unwrap();
+
+        //Already solved
+        let found = Insertions::find(&CubeState::solved().apply_moves(&undo(&skeleton)), &skeleton);
+        assert!(found.solved && found.insertions.is_empty());
+
+        //Two twisted corners aren't a 3-cycle
+        let twisted = CubeState::solved().apply_moves(&parse_moves("[(R' D' R D)2, U]").unwrap()).apply_moves(&undo(&skeleton));
+        let found = Insertions::find(&twisted, &skeleton);
+        assert!(!found.solved && found.insertions.is_empty());
+        assert_eq!(found.solution, skeleton);
+    }
+
+    #[test]
+    fn time_only_runs_out_once()
+    {
+        let mut timer = FmcTimer::new(100.0);
+
+        assert_eq!(timer.time_left(100.0), FMC_TIME_LIMIT);
+        assert!(!timer.expire(100.0 + FMC_TIME_LIMIT - 1.0));
+        assert!(timer.expire(100.0 + FMC_TIME_LIMIT));
+        assert!(!timer.expire(200.0 + FMC_TIME_LIMIT));
+        assert_eq!(timer.time_left(200.0 + FMC_TIME_LIMIT), 0.0);
+    }
+}
@@ -0,0 +1,183 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::fmc::Skeleton;
+use crate::metrics::Metrics;
+use crate::notation::{format_moves, Move};
+use crate::splits::{cfop_splits, Split};
+use crate::state::CubeState;
+
+/*
+    Moves made on a cube since it was last scrambled (or had its state set), each with the time it finished.
+    When the cube is solved from a scramble the history is kept as a solve record.
+    Moves can also be made on the inverse scramble (NISS, see fmc.rs), which are written in brackets in the skeleton
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const MOVE_HISTORY: &'static str = r#"
+/**
+ * Moves made since the cube was last scrambled, with each time in seconds after the first move finished.
+ * Inverse moves were made on the inverse scramble, and are in brackets in the skeleton
+ */
+export interface MoveHistory {
+    scramble: string;
+    moves: { move: string; time: number; inverse: boolean }[];
+    skeleton: string;
+    metrics: MoveMetrics;
+}
+
+/** Step of a CFOP solve (cross, F2L 1 to 4, OLL or PLL) with its moves and how long it took in seconds */
+export interface SolveSplit {
+    step: string;
+    moves: string;
+    moveCount: number;
+    time: number;
+}
+
+/** History of a solve, along with how long it took (in seconds from the first move to the last). Solves using NISS have no splits */
+export interface SolveRecord extends MoveHistory {
+    solution: string;
+    duration: number;
+    splits: SolveSplit[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "MoveHistory")]
+    pub type MoveHistory;
+
+    #[wasm_bindgen(typescript_type = "SolveRecord[]")]
+    pub type SolveRecords;
+}
+
+// A move along with the time (in seconds, see get_current_time) it finished
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimedMove {
+    pub turn: Move,
+    pub time: f64,
+    pub inverse: bool, // True if the move was made on the inverse scramble
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    pub start: CubeState, // State the moves were made from
+    pub scramble: Vec<Move>,
+    pub moves: Vec<TimedMove>,
+    pub inverse: bool, // True while moves are being made on the inverse scramble
+    solving: bool, // True while the cube is unsolved from a scramble (or a state that was set) ie. a solve is in progress
+}
+
+impl History {
+
+    // Start a new history, from a cube which has just been scrambled or set to a state
+    pub fn restart(&mut self, start: &CubeState, scramble: &[Move], solved: bool)
+    {
+        self.start = start.clone();
+        self.scramble = scramble.to_vec();
+        self.moves.clear();
+        self.inverse = false;
+        self.solving = !solved;
+    }
+
+    // Record a finished move. Returns a record of the solve if the move solved the cube
+    pub fn record(&mut self, turn: Move, time: f64, solved: bool) -> Option<SolveRecord>
+    {
+        self.moves.push(TimedMove { turn, time, inverse: self.inverse });
+
+        if !(solved && self.solving) { return None; }
+
+        //Steps can only be split from moves made one after another on the scramble
+        self.solving = false;
+        let splits = match self.moves.iter().any(|m| m.inverse) {
+            true  => Vec::new(),
+            false => cfop_splits(&self.start, &self.moves),
+        };
+        Some(SolveRecord { splits, history: self.clone() })
+    }
+
+    pub fn turns(&self) -> Vec<Move>
+    {
+        self.moves.iter().map(|m| m.turn).collect()
+    }
+
+    // Moves made on the scramble and on the inverse scramble
+    pub fn skeleton(&self) -> Skeleton
+    {
+        let side = |inverse: bool| self.moves.iter().filter(|m| m.inverse == inverse).map(|m| m.turn).collect();
+        Skeleton { normal: side(false), inverse: side(true) }
+    }
+
+    // Moves which take the scrambled cube to where the moves have got it, with any made on the inverse scramble undone at the end
+    pub fn solution(&self) -> Vec<Move>
+    {
+        self.skeleton().solution()
+    }
+
+    // Get the state of the cube with the moves made so far, seen from the scramble or the inverse scramble
+    pub fn side_state(&self, inverse: bool) -> CubeState
+    {
+        let unscrambled = self.start.apply_moves(&self.scramble.iter().rev().map(|m| m.inverse()).collect::<Vec<_>>());
+        self.skeleton().state(&unscrambled, &self.scramble, inverse)
+    }
+
+    pub fn metrics(&self) -> Metrics
+    {
+        Metrics::of(&self.turns())
+    }
+
+    // Time (in seconds) from the first move to the last
+    pub fn duration(&self) -> f64
+    {
+        match (self.moves.first(), self.moves.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    // Method to build the plain JS object given to JS (see MoveHistory)
+    pub fn to_js(&self) -> JsValue
+    {
+        let start = self.moves.first().map_or(0.0, |m| m.time);
+
+        let moves = self.moves.iter().map(|m| {
+            let object = js_sys::Object::new();
+            js_sys::Reflect::set(&object, &JsValue::from_str("move"), &JsValue::from_str(&m.turn.to_string())).ok();
+            js_sys::Reflect::set(&object, &JsValue::from_str("time"), &JsValue::from_f64(m.time - start)).ok();
+            js_sys::Reflect::set(&object, &JsValue::from_str("inverse"), &JsValue::from_bool(m.inverse)).ok();
+            object
+        }).collect::<js_sys::Array>();
+
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("scramble", JsValue::from_str(&format_moves(&self.scramble)));
+        set("moves", moves.into());
+        set("skeleton", JsValue::from_str(&self.skeleton().to_string()));
+        set("metrics", self.metrics().to_js());
+
+        object.into()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SolveRecord {
+    pub history: History,
+    pub splits: Vec<Split>, // Solve split into CFOP steps
+}
+
+impl SolveRecord {
+
+    // Method to build the plain JS object given to JS (see SolveRecord)
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = self.history.to_js();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("solution", JsValue::from_str(&format_moves(&self.history.solution())));
+        set("duration", JsValue::from_f64(self.history.duration()));
+        set("splits", self.splits.iter().map(|split| split.to_js()).collect::<js_sys::Array>().into());
+
+        object
+    }
+}
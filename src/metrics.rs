@@ -0,0 +1,136 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::notation::{parse_moves, Layers, Move};
+
+/*
+    Move counts of an algorithm in the usual metrics, so algorithms (and solves) can be compared.
+    HTM (half turn metric)      - any turn of an outer layer is 1 move, slices are 2 (as they are two outer turns)
+    QTM (quarter turn metric)   - as HTM but half turns count twice
+    STM (slice turn metric)     - any turn of any layers is 1 move
+    ETM (execution turn metric) - every move written counts, including whole cube rotations
+    Rotations don't count in the other metrics as they don't change the cube
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const MOVE_METRICS: &'static str = r#"
+/** Move count of an algorithm in the half turn, quarter turn, slice turn and execution turn metrics */
+export interface MoveMetrics {
+    htm: number;
+    qtm: number;
+    stm: number;
+    etm: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "MoveMetrics")]
+    pub type MoveMetrics;
+}
+
+/*
+ * Count the moves of an algorithm in standard notation in each metric. Throws a RubixError if the notation is invalid
+ */
+#[wasm_bindgen(js_name = moveMetrics)]
+pub fn moveMetrics(algorithm: &str) -> Result<MoveMetrics, JsValue>
+{
+    let moves = parse_moves(algorithm)?;
+    Ok(Metrics::of(&moves).to_js().unchecked_into())
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub htm: u32,
+    pub qtm: u32,
+    pub stm: u32,
+    pub etm: u32,
+}
+
+impl Metrics {
+
+    pub fn of(moves: &[Move]) -> Self
+    {
+        let mut metrics = Self::default();
+
+        //Moves which don't turn anything (eg. R4) aren't counted
+        for m in moves.iter().filter(|m| m.quarterTurns != 0) {
+
+            let quarterTurns = if m.quarterTurns == 2 { 2 } else { 1 };
+
+            let outerTurns = match m.layers {
+                Layers::Face(_) | Layers::Wide(_) => 1,
+                Layers::Slice(_)                  => 2,
+                Layers::Rotation(_)               => 0,
+            };
+
+            metrics.htm += outerTurns;
+            metrics.qtm += outerTurns * quarterTurns;
+            metrics.stm += if outerTurns > 0 { 1 } else { 0 };
+            metrics.etm += 1;
+        }
+
+        metrics
+    }
+
+    // Method to build the plain JS object given to JS (see MoveMetrics)
+    pub fn to_js(self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: u32| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &JsValue::from(value)).ok(); };
+
+        set("htm", self.htm);
+        set("qtm", self.qtm);
+        set("stm", self.stm);
+        set("etm", self.etm);
+
+        object.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(algorithm: &str) -> [u32; 4]
+    {
+        let metrics = Metrics::of(&parse_moves(algorithm).unwrap());
+        [metrics.htm, metrics.qtm, metrics.stm, metrics.etm]
+    }
+
+    #[test]
+    fn face_turns()
+    {
+        assert_eq!(metrics("R U R' U'"), [4, 4, 4, 4]);
+        assert_eq!(metrics("R2 U2"), [2, 4, 2, 2]);
+    }
+
+    #[test]
+    fn slices_count_as_two_outer_turns()
+    {
+        assert_eq!(metrics("M"), [2, 2, 1, 1]);
+        assert_eq!(metrics("M2"), [2, 4, 1, 1]);
+        assert_eq!(metrics("E' S"), [4, 4, 2, 2]);
+    }
+
+    #[test]
+    fn wide_moves_count_as_one_turn()
+    {
+        assert_eq!(metrics("Rw"), [1, 1, 1, 1]);
+        assert_eq!(metrics("r2 u'"), [2, 3, 2, 2]);
+    }
+
+    #[test]
+    fn rotations_only_count_in_etm()
+    {
+        assert_eq!(metrics("x y2 z'"), [0, 0, 0, 3]);
+        assert_eq!(metrics("x R U"), [2, 2, 2, 3]);
+    }
+
+    #[test]
+    fn moves_turning_nothing_are_not_counted()
+    {
+        assert_eq!(metrics("R4 U0"), [0, 0, 0, 0]);
+        assert_eq!(metrics(""), [0, 0, 0, 0]);
+    }
+}
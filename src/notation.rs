@@ -0,0 +1,433 @@
+
+use std::fmt;
+
+use crate::error::RubixError;
+use crate::state::Face;
+
+/*
+    Moves written in standard cube notation (eg. R U' F2 Rw M x).
+    Note: These are the faces of the logical cube (see state.rs) so F is the orange side closest to the camera
+*/
+
+// Middle layers, named after the face whose direction they turn in (M follows L, E follows D, S follows F)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slice { M, E, S }
+
+// Axes the whole cube is rotated around, named after the face whose direction they turn in (x follows R, y follows U, z follows F)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis { X, Y, Z }
+
+// Which layers of the cube a move turns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layers {
+    Face(Face),     // Outer layer of a face eg. R
+    Wide(Face),     // Outer two layers of a face eg. Rw (or r)
+    Slice(Slice),   // Middle layer eg. M
+    Rotation(Axis), // Whole cube eg. x
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub layers: Layers,
+    pub quarterTurns: i32, // Clockwise quarter turns, between 0 and 3 (ie. 3 is a prime move)
+}
+
+impl Move {
+
+    pub fn new(layers: Layers, quarterTurns: i32) -> Self
+    {
+        Self { layers, quarterTurns: quarterTurns.rem_euclid(4) }
+    }
+
+    pub fn inverse(self) -> Self
+    {
+        Self::new(self.layers, -self.quarterTurns)
+    }
+
+    // Get the face the move turns clockwise around, along with which layers (counted from that face) it turns
+    pub fn turn(self) -> (Face, [bool; 3])
+    {
+        match self.layers {
+            Layers::Face(face)        => (face, [true, false, false]),
+            Layers::Wide(face)        => (face, [true, true, false]),
+            Layers::Slice(Slice::M)   => (Face::L, [false, true, false]),
+            Layers::Slice(Slice::E)   => (Face::D, [false, true, false]),
+            Layers::Slice(Slice::S)   => (Face::F, [false, true, false]),
+            Layers::Rotation(Axis::X) => (Face::R, [true; 3]),
+            Layers::Rotation(Axis::Y) => (Face::U, [true; 3]),
+            Layers::Rotation(Axis::Z) => (Face::F, [true; 3]),
+        }
+    }
+}
+
+impl fmt::Display for Move {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.layers {
+            Layers::Face(face)        => write!(f, "{:?}", face)?,
+            Layers::Wide(face)        => write!(f, "{:?}w", face)?,
+            Layers::Slice(slice)      => write!(f, "{:?}", slice)?,
+            Layers::Rotation(Axis::X) => write!(f, "x")?,
+            Layers::Rotation(Axis::Y) => write!(f, "y")?,
+            Layers::Rotation(Axis::Z) => write!(f, "z")?,
+        }
+
+        //Moves which don't turn anything (eg. R4) keep their count, so they aren't read back as a turn
+        match self.quarterTurns {
+            0 => write!(f, "0"),
+            2 => write!(f, "2"),
+            3 => write!(f, "'"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/*
+    Function to write out a sequence of moves separated by spaces (eg. "R U R' U'")
+*/
+pub fn format_moves(moves: &[Move]) -> String
+{
+    moves.iter().map(|m| m.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/*
+    Algorithms written with grouping as well as moves, kept as written so they can be turned back into text:
+    (A)n repeats A n times, [A, B] is the commutator A B A' B' and [A: B] is the conjugate A B A'.
+    Groups, commutators and conjugates can be nested and followed by a number of repeats and/or a prime (eg. [R, U]2')
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    Move(Move),
+    Group(Vec<Node>, i32),                  // Repeats (negative for a prime ie. inverted)
+    Commutator(Vec<Node>, Vec<Node>, i32),
+    Conjugate(Vec<Node>, Vec<Node>, i32),
+}
+
+//Most moves an algorithm can expand to, so repeats (eg. ((R)999)999) can't run out of memory
+const MAX_MOVES: usize = 100_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Algorithm {
+    pub nodes: Vec<Node>,
+}
+
+impl Algorithm {
+
+    pub fn parse(algorithm: &str) -> Result<Self, RubixError>
+    {
+        let mut chars = algorithm.char_indices().peekable();
+        let nodes = parse_sequence(&mut chars, &[])?;
+
+        let parsed = Self { nodes };
+        if sequence_length(&parsed.nodes) > MAX_MOVES {
+            return Err(RubixError::InvalidAlgorithm(format!("expands to more than {} moves", MAX_MOVES)));
+        }
+
+        Ok(parsed)
+    }
+
+    // Get the moves of the algorithm with every group, commutator and conjugate written out
+    pub fn expand(&self) -> Vec<Move>
+    {
+        expand_sequence(&self.nodes)
+    }
+
+    // Get the algorithm which undoes this one (eg. [R, U] becomes [U, R] and (R U)3 becomes (R U)3')
+    pub fn inverse(&self) -> Self
+    {
+        Self { nodes: invert_sequence(&self.nodes) }
+    }
+
+    // Get the algorithm with every move replaced, keeping its groups, commutators and conjugates
+    pub fn map_moves(&self, f: &impl Fn(Move) -> Move) -> Self
+    {
+        Self { nodes: map_sequence(&self.nodes, f) }
+    }
+}
+
+impl fmt::Display for Algorithm {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write_sequence(f, &self.nodes)
+    }
+}
+
+fn expand_sequence(nodes: &[Node]) -> Vec<Move>
+{
+    let mut moves = Vec::new();
+
+    for node in nodes {
+
+        let (once, repeats) = match node {
+            Node::Move(m) => { moves.push(*m); continue; },
+            Node::Group(body, repeats) => (expand_sequence(body), *repeats),
+            Node::Commutator(a, b, repeats) => {
+                let (a, b) = (expand_sequence(a), expand_sequence(b));
+                ([a.clone(), b.clone(), invert_moves(&a), invert_moves(&b)].concat(), *repeats)
+            },
+            Node::Conjugate(a, b, repeats) => {
+                let a = expand_sequence(a);
+                ([a.clone(), expand_sequence(b), invert_moves(&a)].concat(), *repeats)
+            },
+        };
+
+        let once = if repeats < 0 { invert_moves(&once) } else { once };
+        for _ in 0..repeats.unsigned_abs() {
+            moves.extend_from_slice(&once);
+        }
+    }
+
+    moves
+}
+
+// Count of moves a sequence expands to (without expanding it)
+fn sequence_length(nodes: &[Node]) -> usize
+{
+    nodes.iter().fold(0usize, |length, node| {
+        let (once, repeats) = match node {
+            Node::Move(_) => (1, 1),
+            Node::Group(body, repeats) => (sequence_length(body), *repeats),
+            Node::Commutator(a, b, repeats) => (2 * (sequence_length(a) + sequence_length(b)), *repeats),
+            Node::Conjugate(a, b, repeats) => (2 * sequence_length(a) + sequence_length(b), *repeats),
+        };
+        length.saturating_add(once.saturating_mul(repeats.unsigned_abs() as usize))
+    })
+}
+
+fn invert_moves(moves: &[Move]) -> Vec<Move>
+{
+    moves.iter().rev().map(|m| m.inverse()).collect()
+}
+
+fn invert_sequence(nodes: &[Node]) -> Vec<Node>
+{
+    nodes.iter().rev().map(|node| match node {
+        Node::Move(m) => Node::Move(m.inverse()),
+        Node::Group(body, repeats) => Node::Group(body.clone(), -repeats),
+        Node::Commutator(a, b, repeats) => Node::Commutator(b.clone(), a.clone(), *repeats),
+        Node::Conjugate(a, b, repeats) => Node::Conjugate(a.clone(), invert_sequence(b), *repeats),
+    }).collect()
+}
+
+fn map_sequence(nodes: &[Node], f: &impl Fn(Move) -> Move) -> Vec<Node>
+{
+    nodes.iter().map(|node| match node {
+        Node::Move(m) => Node::Move(f(*m)),
+        Node::Group(body, repeats) => Node::Group(map_sequence(body, f), *repeats),
+        Node::Commutator(a, b, repeats) => Node::Commutator(map_sequence(a, f), map_sequence(b, f), *repeats),
+        Node::Conjugate(a, b, repeats) => Node::Conjugate(map_sequence(a, f), map_sequence(b, f), *repeats),
+    }).collect()
+}
+
+fn write_sequence(f: &mut fmt::Formatter, nodes: &[Node]) -> fmt::Result
+{
+    for (i, node) in nodes.iter().enumerate() {
+
+        if i > 0 { write!(f, " ")?; }
+
+        let repeats = match node {
+            Node::Move(m) => { write!(f, "{}", m)?; continue; },
+            Node::Group(body, repeats) => {
+                write!(f, "(")?;
+                write_sequence(f, body)?;
+                write!(f, ")")?;
+                *repeats
+            },
+            Node::Commutator(a, b, repeats) | Node::Conjugate(a, b, repeats) => {
+                write!(f, "[")?;
+                write_sequence(f, a)?;
+                write!(f, "{} ", if matches!(node, Node::Commutator(..)) { "," } else { ":" })?;
+                write_sequence(f, b)?;
+                write!(f, "]")?;
+                *repeats
+            },
+        };
+
+        if repeats.abs() != 1 { write!(f, "{}", repeats.abs())?; }
+        if repeats < 0 { write!(f, "'")?; }
+    }
+
+    Ok(())
+}
+
+/*
+    Function to parse a sequence of moves (eg. "R U R' U'"). Spaces between moves are optional.
+    Each move may be followed by a number of turns and/or a prime (eg. R2, R', R2', R3).
+    Groups, commutators and conjugates (see Algorithm) are written out into the moves they make
+*/
+pub fn parse_moves(algorithm: &str) -> Result<Vec<Move>, RubixError>
+{
+    Ok(Algorithm::parse(algorithm)?.expand())
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+// Parse nodes up until the end of the algorithm or one of the closing characters (which is left to be read by the caller)
+fn parse_sequence(chars: &mut Chars, closing: &[char]) -> Result<Vec<Node>, RubixError>
+{
+    let mut nodes = Vec::new();
+
+    while let Some(&(position, c)) = chars.peek() {
+
+        if c.is_whitespace() { chars.next(); continue; }
+        if closing.contains(&c) { break; }
+
+        let node = match c {
+
+            '(' => {
+                chars.next();
+                let body = parse_sequence(chars, &[')'])?;
+                expect(chars, &[')'], c, position)?;
+                Node::Group(body, parse_repeats(chars))
+            },
+
+            '[' => {
+                chars.next();
+                let a = parse_sequence(chars, &[',', ':'])?;
+                let separator = expect(chars, &[',', ':'], c, position)?;
+                let b = parse_sequence(chars, &[']'])?;
+                expect(chars, &[']'], c, position)?;
+
+                let repeats = parse_repeats(chars);
+                if separator == ',' { Node::Commutator(a, b, repeats) } else { Node::Conjugate(a, b, repeats) }
+            },
+
+            _ => Node::Move(parse_move(chars)?),
+        };
+
+        nodes.push(node);
+    }
+
+    Ok(nodes)
+}
+
+// Read one of the expected characters, which must come before the end of the group opened at the given position
+fn expect(chars: &mut Chars, expected: &[char], opening: char, position: usize) -> Result<char, RubixError>
+{
+    match chars.next() {
+        Some((_, c)) if expected.contains(&c) => Ok(c),
+        Some((at, c)) => Err(RubixError::InvalidAlgorithm(format!("unexpected \"{}\" at position {}", c, at))),
+        None => Err(RubixError::InvalidAlgorithm(format!("\"{}\" at position {} is never closed", opening, position))),
+    }
+}
+
+// Read the number of repeats (defaults to 1) and prime following a group
+fn parse_repeats(chars: &mut Chars) -> i32
+{
+    let mut repeats: Option<i32> = None;
+    while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+        repeats = Some(repeats.unwrap_or(0).saturating_mul(10).saturating_add(digit.to_digit(10).unwrap_or(0) as i32));
+    }
+    let repeats = repeats.unwrap_or(1);
+
+    if chars.next_if(|(_, c)| *c == '\'' || *c == '’').is_some() { -repeats } else { repeats }
+}
+
+fn parse_move(chars: &mut Chars) -> Result<Move, RubixError>
+{
+    let Some((position, c)) = chars.next() else { return Err(RubixError::InvalidAlgorithm("missing move".to_string())); };
+
+    let layers = match (c, Face::from_letter(c.to_ascii_uppercase())) {
+
+        //Upper case faces turn a single layer unless followed by w, lower case ones are always wide
+        (_, Some(face)) if c.is_ascii_uppercase() => {
+            if chars.next_if(|(_, c)| *c == 'w').is_some() { Layers::Wide(face) } else { Layers::Face(face) }
+        },
+        (_, Some(face)) => Layers::Wide(face),
+        ('M', _) => Layers::Slice(Slice::M),
+        ('E', _) => Layers::Slice(Slice::E),
+        ('S', _) => Layers::Slice(Slice::S),
+        ('x', _) => Layers::Rotation(Axis::X),
+        ('y', _) => Layers::Rotation(Axis::Y),
+        ('z', _) => Layers::Rotation(Axis::Z),
+        _ => return Err(RubixError::InvalidAlgorithm(format!("unexpected \"{}\" at position {}", c, position))),
+    };
+
+    //Number of turns (defaults to 1)
+    let mut turns: Option<i32> = None;
+    while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+        turns = Some((turns.unwrap_or(0) * 10 + digit.to_digit(10).unwrap_or(0) as i32) % 4);
+    }
+    let mut turns = turns.unwrap_or(1);
+
+    //Prime reverses direction (both ascii and typographic apostrophes are accepted)
+    if chars.next_if(|(_, c)| *c == '\'' || *c == '’').is_some() { turns = -turns; }
+
+    Ok(Move::new(layers, turns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::seeded_random;
+    use crate::state::CubeState;
+
+    fn expanded(algorithm: &str) -> String
+    {
+        format_moves(&parse_moves(algorithm).unwrap())
+    }
+
+    #[test]
+    fn parses_every_kind_of_move()
+    {
+        assert_eq!(expanded("R U2 F' Rw r' M E2 S' x y' z2"), "R U2 F' Rw Rw' M E2 S' x y' z2");
+        assert_eq!(expanded("RUR'U'"), "R U R' U'");
+        assert_eq!(expanded("R3 U2'"), "R' U2");
+    }
+
+    #[test]
+    fn expands_groups_commutators_and_conjugates()
+    {
+        assert_eq!(expanded("(R U R' U')2"), "R U R' U' R U R' U'");
+        assert_eq!(expanded("[R, U]"), "R U R' U'");
+        assert_eq!(expanded("[R: U]"), "R U R'");
+        assert_eq!(expanded("[R U R', D]"), "R U R' D R U' R' D'");
+        assert_eq!(expanded("[R, U]'"), "U R U' R'");
+        assert_eq!(expanded("[F: [R, U]]"), "F R U R' U' F'");
+        assert_eq!(expanded("(R)3'"), "R' R' R'");
+    }
+
+    #[test]
+    fn round_trips_to_text()
+    {
+        for algorithm in ["R U R' U'", "(R U R' U')3", "[R U R', D]", "[R: U]", "[R, U]2'", "[F: [R, U]] (M' U)4 x2"] {
+            let parsed = Algorithm::parse(algorithm).unwrap();
+            assert_eq!(parsed.to_string(), algorithm);
+            assert_eq!(Algorithm::parse(&parsed.to_string()).unwrap(), parsed);
+        }
+
+        //Moves which turn nothing still turn nothing once written out
+        for (algorithm, written) in [("R4", "R0"), ("U0", "U0"), ("Rw0", "Rw0"), ("x4", "x0"), ("M8' (R4 U)", "M0 (R0 U)")] {
+            let parsed = Algorithm::parse(algorithm).unwrap();
+            assert_eq!(parsed.to_string(), written);
+            assert_eq!(Algorithm::parse(written).unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_algorithms()
+    {
+        for algorithm in ["Q", "[R, U", "(R U", "R U)", "[R U]", "((R)999)999"] {
+            assert!(Algorithm::parse(algorithm).is_err(), "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_algorithm()
+    {
+        assert_eq!(Algorithm::parse("[R, U]").unwrap().inverse().to_string(), "[U, R]");
+        assert_eq!(Algorithm::parse("(R U)3").unwrap().inverse().to_string(), "(R U)3'");
+
+        let mut random = seeded_random(39);
+        for algorithm in ["[R U R', D]2", "[F: [R, U]] (M' U)4'", "x (R U2 R')2 [Rw: E]"] {
+            let parsed = Algorithm::parse(algorithm).unwrap();
+            let start = CubeState::solved().apply_moves(&crate::scramble::random_scramble(10, &mut random));
+
+            let done = start.apply_moves(&parsed.expand());
+            assert_eq!(done.apply_moves(&parsed.inverse().expand()), start, "{}", algorithm);
+            assert_eq!(parsed.inverse().inverse(), parsed);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::notation::Algorithm;
+use crate::state::{cross, dot, slot_index, slot_position, CubeState, Face, IVec3};
+
+/*
+    The cube as a permutation of its pieces: which piece is in each corner, edge and center position, and how it is oriented.
+    Used to find how an algorithm moves pieces around (as cycles) and how many times it must be repeated to get back to solved
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const ALGORITHM_ANALYSIS: &'static str = r#"
+/** Pieces (named by position eg. "UFR") moved by an algorithm, each going to the position after it and the last to the first */
+export interface CornerCycle { pieces: string[]; twist: number }   // Clockwise thirds of a turn the pieces are twisted by once around the cycle
+export interface EdgeCycle { pieces: string[]; flipped: boolean }
+export interface CenterCycle { pieces: string[] }
+
+/** How an algorithm moves pieces, and how many times it must be done to get back to where it started */
+export interface AlgorithmAnalysis {
+    order: number;
+    affected: string[];
+    corners: CornerCycle[];
+    edges: EdgeCycle[];
+    centers: CenterCycle[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AlgorithmAnalysis")]
+    pub type AlgorithmAnalysis;
+}
+
+/*
+ * Find the order and cycles of an algorithm in standard notation. Throws a RubixError if the notation is invalid
+ */
+#[wasm_bindgen(js_name = analyzeAlgorithm)]
+pub fn analyzeAlgorithm(algorithm: &str) -> Result<AlgorithmAnalysis, JsValue>
+{
+    let moves = Algorithm::parse(algorithm)?.expand();
+
+    let state = CubeState::solved().apply_moves(&moves);
+
+    Ok(Permutation::from_state(&state).to_js().unchecked_into())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceKind { Corner, Edge, Center }
+
+impl PieceKind {
+
+    // Orientations a piece can have (centers aren't counted as they look the same however they are turned)
+    fn orientations(self) -> u8
+    {
+        match self {
+            PieceKind::Corner => 3,
+            PieceKind::Edge   => 2,
+            PieceKind::Center => 1,
+        }
+    }
+}
+
+// Piece in a position, given by the index of its home position and how far it is twisted (or flipped) from its home orientation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Piece {
+    pub home: usize,
+    pub orientation: u8,
+}
+
+// Pieces moved round in a loop, along with the orientation they pick up going once around it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub kind: PieceKind,
+    pub positions: Vec<usize>,
+    pub orientation: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permutation {
+    pub corners: Vec<Piece>, // Indexed by position (see positions)
+    pub edges: Vec<Piece>,
+    pub centers: Vec<Piece>,
+}
+
+impl Permutation {
+
+    pub fn from_state(state: &CubeState) -> Self
+    {
+        let pieces = |kind: PieceKind| positions(kind).iter().map(|position| {
+
+            let faces = ordered_faces(*position);
+            let (home, _) = slot_position(state.sticker(slot_index(*position, faces[0])));
+
+            //Orientation is which of the faces (going counter-clockwise around corners) the piece's reference sticker is on
+            let orientation = faces.iter()
+                .position(|face| reference_faces(home).first() == Some(&slot_position(state.sticker(slot_index(*position, *face))).1))
+                .unwrap_or(0) as u8;
+
+            Piece { home: positions(kind).iter().position(|p| *p == home).unwrap_or(0), orientation }
+        }).collect();
+
+        Self { corners: pieces(PieceKind::Corner), edges: pieces(PieceKind::Edge), centers: pieces(PieceKind::Center) }
+    }
+
+    // Get the cycles of pieces which are moved or twisted
+    pub fn cycles(&self) -> Vec<Cycle>
+    {
+        let mut cycles = Vec::new();
+
+        for (kind, pieces) in [(PieceKind::Corner, &self.corners), (PieceKind::Edge, &self.edges), (PieceKind::Center, &self.centers)] {
+
+            //Where each piece (by home position) has gone
+            let mut destination = vec![0; pieces.len()];
+            for (position, piece) in pieces.iter().enumerate() { destination[piece.home] = position; }
+
+            let mut visited = vec![false; pieces.len()];
+
+            for start in 0..pieces.len() {
+
+                let mut cycle = Cycle { kind, positions: Vec::new(), orientation: 0 };
+                let mut i = start;
+
+                while !visited[i] {
+                    visited[i] = true;
+                    cycle.positions.push(i);
+                    i = destination[i];
+                    cycle.orientation = (cycle.orientation + pieces[i].orientation) % kind.orientations();
+                }
+
+                if cycle.positions.len() > 1 || cycle.orientation != 0 { cycles.push(cycle); }
+            }
+        }
+
+        cycles
+    }
+
+    // Get how many times the permutation must be repeated to get back to solved
+    pub fn order(&self) -> u64
+    {
+        self.cycles().iter().fold(1, |order, cycle| {
+            //Pieces only get home with their orientation once the orientation picked up around the cycle adds up to nothing
+            let repeats = if cycle.orientation == 0 { 1 } else { cycle.kind.orientations() as u64 };
+            lcm(order, cycle.positions.len() as u64 * repeats)
+        })
+    }
+
+    // Method to build the plain JS object given to JS (see AlgorithmAnalysis)
+    pub fn to_js(&self) -> JsValue
+    {
+        let cycles = self.cycles();
+        let names = |cycle: &Cycle| cycle.positions.iter().map(|i| JsValue::from_str(&name(positions(cycle.kind)[*i]))).collect::<js_sys::Array>();
+
+        let list = |kind: PieceKind| cycles.iter().filter(|cycle| cycle.kind == kind).map(|cycle| {
+
+            let object = js_sys::Object::new();
+            js_sys::Reflect::set(&object, &JsValue::from_str("pieces"), &names(cycle)).ok();
+
+            match kind {
+                //Orientation counts counter-clockwise thirds, so flip it round to give clockwise twists
+                PieceKind::Corner => { js_sys::Reflect::set(&object, &JsValue::from_str("twist"), &JsValue::from((3 - cycle.orientation) % 3)).ok(); },
+                PieceKind::Edge   => { js_sys::Reflect::set(&object, &JsValue::from_str("flipped"), &JsValue::from_bool(cycle.orientation == 1)).ok(); },
+                PieceKind::Center => (),
+            }
+            object
+        }).collect::<js_sys::Array>();
+
+        let affected = cycles.iter().flat_map(|cycle| names(cycle).to_vec()).collect::<js_sys::Array>();
+
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("order", JsValue::from_f64(self.order() as f64));
+        set("affected", affected.into());
+        set("corners", list(PieceKind::Corner).into());
+        set("edges", list(PieceKind::Edge).into());
+        set("centers", list(PieceKind::Center).into());
+
+        object.into()
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64
+{
+    let gcd = |mut a: u64, mut b: u64| { while b != 0 { (a, b) = (b, a % b); } a };
+    a / gcd(a, b) * b
+}
+
+// Get the positions of every piece of a kind, in a fixed order
+pub fn positions(kind: PieceKind) -> Vec<IVec3>
+{
+    let count = match kind { PieceKind::Corner => 3, PieceKind::Edge => 2, PieceKind::Center => 1 };
+
+    (0..27)
+        .map(|i| [(i % 3) - 1, ((i / 3) % 3) - 1, (i / 9) - 1])
+        .filter(|position| position.iter().filter(|x| **x != 0).count() == count)
+        .collect()
+}
+
+// Faces of a position which reference stickers are measured from: U or D first, then F or B, then R or L
+fn reference_faces(position: IVec3) -> Vec<Face>
+{
+    [Face::U, Face::D, Face::F, Face::B, Face::R, Face::L].into_iter()
+        .filter(|face| dot(position, face.normal()) == 1)
+        .collect()
+}
+
+// Faces of a position starting with its reference face, going counter-clockwise (seen from outside) around corners
+fn ordered_faces(position: IVec3) -> Vec<Face>
+{
+    let mut faces = reference_faces(position);
+
+    if faces.len() == 3 && dot(cross(faces[0].normal(), faces[1].normal()), faces[2].normal()) != 1 {
+        faces.swap(1, 2);
+    }
+
+    faces
+}
+
+// Name of a position (eg. UFR, UF or U)
+pub fn name(position: IVec3) -> String
+{
+    reference_faces(position).iter().map(|face| face.letter()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_moves;
+
+    fn permutation(algorithm: &str) -> Permutation
+    {
+        Permutation::from_state(&CubeState::solved().apply_moves(&parse_moves(algorithm).unwrap()))
+    }
+
+    fn cycles(algorithm: &str, kind: PieceKind) -> Vec<(usize, u8)>
+    {
+        let mut cycles: Vec<(usize, u8)> = permutation(algorithm).cycles().into_iter()
+            .filter(|cycle| cycle.kind == kind)
+            .map(|cycle| (cycle.positions.len(), cycle.orientation))
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    #[test]
+    fn known_orders()
+    {
+        for (algorithm, order) in [("", 1), ("R", 4), ("R2", 2), ("R U", 105), ("R U R' U'", 6), ("R U R' U R U2 R'", 6),
+                                   ("R U R' U' R' F R2 U' R' U' R U R' F'", 2), ("M", 4), ("x", 4), ("[R U R', D]", 3)] {
+            assert_eq!(permutation(algorithm).order(), order, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn order_repeats_back_to_solved()
+    {
+        for algorithm in ["R U", "R U2 D' B D'", "F R' M2 U"] {
+            let order = permutation(algorithm).order() as usize;
+            let moves = parse_moves(algorithm).unwrap();
+            let repeated: Vec<_> = std::iter::repeat_n(moves, order).flatten().collect();
+
+            assert!(CubeState::solved().apply_moves(&repeated).is_solved(false), "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn cycle_structure()
+    {
+        //T perm swaps two corners and two edges
+        let t = "R U R' U' R' F R2 U' R' U' R U R' F'";
+        assert_eq!(cycles(t, PieceKind::Corner), vec![(2, 0)]);
+        assert_eq!(cycles(t, PieceKind::Edge), vec![(2, 0)]);
+
+        //Pure commutator is a 3-cycle of corners, and a face turn is a 4-cycle of each
+        assert_eq!(cycles("[R U R', D]", PieceKind::Corner), vec![(3, 0)]);
+        assert!(cycles("[R U R', D]", PieceKind::Edge).is_empty());
+        assert_eq!(cycles("U", PieceKind::Corner), vec![(4, 0)]);
+
+        //Sune swaps two pairs of corners (twisting them) and cycles three edges
+        let sune = "R U R' U R U2 R'";
+        assert_eq!(cycles(sune, PieceKind::Corner), vec![(2, 1), (2, 2)]);
+        assert_eq!(cycles(sune, PieceKind::Edge), vec![(3, 0)]);
+
+        //Twisting one corner one way and another the other way leaves them in place
+        let twist = "[(R' D' R D)2, U]";
+        assert_eq!(cycles(twist, PieceKind::Corner), vec![(1, 1), (1, 2)]);
+        assert!(cycles(twist, PieceKind::Edge).is_empty());
+        assert_eq!(permutation(twist).order(), 3);
+
+        //Edges flipped going round a cycle of F turns are flipped back by the time they get home
+        assert_eq!(cycles("F", PieceKind::Edge), vec![(4, 0)]);
+        assert_eq!(cycles("M' U M' U M' U2 M U M U M U2", PieceKind::Edge), vec![(1, 1), (1, 1)]);
+        assert!(permutation("").cycles().is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::permutation::{positions, PieceKind};
+use crate::state::{dot, slot_index, CubeState, Face, IVec3};
+
+/*
+    Progress through the first steps of a CFOP solve (the cross and the F2L pairs) for every colour the cross could be on.
+    Pieces are tracked by their colours relative to the centers, so it doesn't matter how the cube is held
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const CROSS_PROGRESS: &'static str = r#"
+/** Progress of the cross and F2L on one colour. Paired pairs have their corner and edge joined up but not yet in their slot */
+export interface CrossProgress {
+    colour: string;
+    crossEdges: number;
+    solvedPairs: number;
+    pairedPairs: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CrossProgress[]")]
+    pub type CrossProgressList;
+}
+
+// Names of the colours, by atlas colour index (see Face::colour_index)
+const COLOUR_NAMES: [&str; 6] = ["red", "orange", "yellow", "green", "blue", "white"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrossProgress {
+    pub colour: Face,      // Face the cross colour started on (ie. its colour), wherever its center is now
+    pub crossEdges: u8,    // Cross edges in place, from 0 to 4
+    pub solvedPairs: u8,   // F2L pairs in their slot
+    pub pairedPairs: u8,   // F2L pairs whose corner and edge are joined up outside their slot
+}
+
+impl CrossProgress {
+
+    // Method to build the plain JS object given to JS (see CrossProgress)
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("colour", JsValue::from_str(COLOUR_NAMES[self.colour.colour_index() as usize]));
+        set("crossEdges", JsValue::from(self.crossEdges));
+        set("solvedPairs", JsValue::from(self.solvedPairs));
+        set("pairedPairs", JsValue::from(self.pairedPairs));
+
+        object.into()
+    }
+}
+
+// Get the progress of every cross colour
+pub fn cross_progress(state: &CubeState) -> Vec<CrossProgress>
+{
+    Face::ALL.into_iter().map(|colour| progress_for(state, colour)).collect()
+}
+
+// Get the progress of a cross colour, from the face its center is on
+pub fn progress_for(state: &CubeState, colour: Face) -> CrossProgress
+{
+    let mut progress = CrossProgress { colour, crossEdges: 0, solvedPairs: 0, pairedPairs: 0 };
+    let cross = center(state, colour);
+
+    //Colours around the cross (ie. the sides of the cube when the cross is on the bottom)
+    let sides: Vec<Face> = Face::ALL.into_iter().filter(|face| dot(face.normal(), cross.normal()) == 0).collect();
+
+    for side in &sides {
+        if solved(state, &[cross, *side]) { progress.crossEdges += 1; }
+    }
+
+    //Each pair of neighbouring sides has a slot for a corner (with the cross colour) and an edge
+    for (i, a) in sides.iter().enumerate() {
+        for b in sides[i + 1..].iter().filter(|b| dot(a.normal(), b.normal()) == 0) {
+
+            let (corner, edge) = ([cross, *a, *b], [*a, *b]);
+
+            if solved(state, &corner) && solved(state, &edge) {
+                progress.solvedPairs += 1;
+            } else if paired(state, &corner, &edge) {
+                progress.pairedPairs += 1;
+            }
+        }
+    }
+
+    progress
+}
+
+// Check the face opposite the cross colour is all one colour (ie. the last layer is oriented)
+pub fn last_layer_oriented(state: &CubeState, colour: Face) -> bool
+{
+    let cross = center(state, colour);
+    let Some(last) = Face::ALL.into_iter().find(|face| dot(face.normal(), cross.normal()) == -1) else { return false; };
+
+    (0..9).all(|i| relative_colour(state, last.index() * 9 + i) == last)
+}
+
+// Get the face the center of a colour is on, since the cube may be held any way
+fn center(state: &CubeState, colour: Face) -> Face
+{
+    Face::ALL.into_iter().find(|face| state.sticker_face(face.index() * 9 + 4) == colour).unwrap_or(colour)
+}
+
+// Get the colours (relative to the centers) of the stickers on each face of a position
+fn stickers(state: &CubeState, position: IVec3) -> Vec<(Face, Face)>
+{
+    Face::ALL.into_iter()
+        .filter(|face| dot(position, face.normal()) == 1)
+        .map(|face| (face, relative_colour(state, slot_index(position, face))))
+        .collect()
+}
+
+// Get the face whose center is the colour of the sticker in a slot (so the cube can be held any way)
+pub fn relative_colour(state: &CubeState, slot: usize) -> Face
+{
+    let colour = state.sticker_face(slot);
+    Face::ALL.into_iter().find(|face| state.sticker_face(face.index() * 9 + 4) == colour).unwrap_or(colour)
+}
+
+// Check the piece with the given colours is in the position between those centers with every sticker matching
+fn solved(state: &CubeState, colours: &[Face]) -> bool
+{
+    let home = colours.iter().fold([0, 0, 0], |home, colour| std::array::from_fn(|i| home[i] + colour.normal()[i]));
+    stickers(state, home).iter().all(|(face, colour)| face == colour)
+}
+
+// Find where the piece with the given colours is, along with its stickers
+fn find(state: &CubeState, colours: &[Face]) -> Option<(IVec3, Vec<(Face, Face)>)>
+{
+    let kind = if colours.len() == 3 { PieceKind::Corner } else { PieceKind::Edge };
+
+    positions(kind).into_iter()
+        .map(|position| (position, stickers(state, position)))
+        .find(|(_, pieceStickers)| colours.iter().all(|colour| pieceStickers.iter().any(|(_, sticker)| sticker == colour)))
+}
+
+// Check a corner and edge are next to each other with the stickers they share matching (ie. they can be put in together)
+fn paired(state: &CubeState, corner: &[Face], edge: &[Face]) -> bool
+{
+    let (Some((_, cornerStickers)), Some((_, edgeStickers))) = (find(state, corner), find(state, edge)) else { return false; };
+
+    edgeStickers.iter().all(|(face, colour)| cornerStickers.contains(&(*face, *colour)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_moves;
+    use crate::scramble::{random_scramble, seeded_random};
+
+    fn counts(state: &CubeState, colour: Face) -> (u8, u8, u8)
+    {
+        let progress = progress_for(state, colour);
+        (progress.crossEdges, progress.solvedPairs, progress.pairedPairs)
+    }
+
+    #[test]
+    fn progress_is_counted_for_each_colour()
+    {
+        let state = CubeState::solved().apply_moves(&parse_moves("R U R' F").unwrap());
+
+        assert_eq!(counts(&CubeState::solved(), Face::D), (4, 4, 0));
+        assert_eq!(counts(&state, Face::U), (1, 1, 0));
+        assert_eq!(counts(&state, Face::D), (3, 2, 2));
+
+        //Still under the same colours with the cross held on the front
+        let rotated = state.apply_moves(&parse_moves("x").unwrap());
+        assert_eq!((counts(&rotated, Face::U), counts(&rotated, Face::D)), ((1, 1, 0), (3, 2, 2)));
+        assert!(last_layer_oriented(&CubeState::solved(), Face::D));
+        assert!(!last_layer_oriented(&state, Face::D));
+    }
+
+    #[test]
+    fn rotations_leave_progress_unchanged()
+    {
+        let mut random = seeded_random(43);
+
+        for _ in 0..10 {
+            let state = CubeState::solved().apply_moves(&random_scramble(8, &mut random));
+
+            for rotation in ["x", "y", "z", "x y2", "z' y"] {
+                let rotated = state.apply_moves(&parse_moves(rotation).unwrap());
+
+                assert_eq!(cross_progress(&rotated), cross_progress(&state), "{}", rotation);
+                assert!(Face::ALL.into_iter().all(|colour| last_layer_oriented(&rotated, colour) == last_layer_oriented(&state, colour)));
+            }
+        }
+    }
+}
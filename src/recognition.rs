@@ -0,0 +1,499 @@
+
+use std::sync::OnceLock;
+
+use wasm_bindgen::prelude::*;
+
+use crate::notation::{format_moves, Algorithm, Axis, Layers, Move};
+use crate::progress::relative_colour;
+use crate::state::{CubeState, Face};
+use crate::transform::rotate_move;
+
+/*
+    Recognising which OLL or PLL case is on the last layer once the first two layers (F2L) are solved.
+    Rather than hand writing the pattern of every case, each one is made by undoing its algorithm on a solved cube
+    (with every combination of U turns before and after). The cube may be held any way up, so long as F2L is solved.
+    The F2L and ZBLL cases built into the trainer are also kept here
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const CASE_RECOGNITION: &'static str = r#"
+/** Last layer case on the cube, with an algorithm (including any U turns needed) to solve it from how the cube is held */
+export interface CaseRecognition {
+    stage: "oll" | "pll" | "solved";
+    name: string;
+    algorithm: string;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CaseRecognition | undefined")]
+    pub type CaseRecognition;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage { Oll, Pll, Solved }
+
+impl Stage {
+
+    fn name(self) -> &'static str
+    {
+        match self {
+            Stage::Oll    => "oll",
+            Stage::Pll    => "pll",
+            Stage::Solved => "solved",
+        }
+    }
+}
+
+// Algorithm for each case (the algorithm solves the case, as seen with the last layer on top)
+pub const OLL_CASES: [&str; 57] = [
+    "R U2 R2 F R F' U2 R' F R F'",
+    "F R U R' U' F' f R U R' U' f'",
+    "f R U R' U' f' U' F R U R' U' F'",
+    "f R U R' U' f' U F R U R' U' F'",
+    "r' U2 R U R' U r",
+    "r U2 R' U' R U' r'",
+    "r U R' U R U2 r'",
+    "r' U' R U' R' U2 r",
+    "R U R' U' R' F R2 U R' U' F'",
+    "R U R' U R' F R F' R U2 R'",
+    "r U R' U R' F R F' R U2 r'",
+    "M' R' U' R U' R' U2 R U' M",
+    "F U R U' R2 F' R U R U' R'",
+    "R' F R U R' F' R F U' F'",
+    "r' U' r R' U' R U r' U r",
+    "r U r' R U R' U' r U' r'",
+    "R U R' U R' F R F' U2 R' F R F'",
+    "r U R' U R U2 r2 U' R U' R' U2 r",
+    "r' R U R U R' U' M' R' F R F'",
+    "r U R' U' M2 U R U' R' U' M'",
+    "R U2 R' U' R U R' U' R U' R'",
+    "R U2 R2 U' R2 U' R2 U2 R",
+    "R2 D' R U2 R' D R U2 R",
+    "r U R' U' r' F R F'",
+    "F' r U R' U' r' F R",
+    "R U2 R' U' R U' R'",
+    "R U R' U R U2 R'",
+    "r U R' U' r' R U R U' R'",
+    "R U R' U' R U' R' F' U' F R U R'",
+    "F R' F R2 U' R' U' R U R' F2",
+    "R' U' F U R U' R' F' R",
+    "L U F' U' L' U L F L'",
+    "R U R' U' R' F R F'",
+    "R U R2 U' R' F R U R U' F'",
+    "R U2 R2 F R F' R U2 R'",
+    "L' U' L U' L' U L U L F' L' F",
+    "F R' F' R U R U' R'",
+    "R U R' U R U' R' U' R' F R F'",
+    "L F' L' U' L U F U' L'",
+    "R' F R U R' U' F' U R",
+    "R U R' U R U2 R' F R U R' U' F'",
+    "R' U' R U' R' U2 R F R U R' U' F'",
+    "F' U' L' U L F",
+    "F U R U' R' F'",
+    "F R U R' U' F'",
+    "R' U' R' F R F' U R",
+    "R' U' R' F R F' R' F R F' U R",
+    "F R U R' U' R U R' U' F'",
+    "r U' r2 U r2 U r2 U' r",
+    "r' U r2 U' r2 U' r2 U r'",
+    "F U R U' R' U R U' R' F'",
+    "R U R' U R U' B U' B' R'",
+    "r' U' R U' R' U R U' R' U2 r",
+    "r U R' U R U' R' U R U2 r'",
+    "R' F R U R U' R2 F' R2 U' R' U R U R'",
+    "r' U' r U' R' U R U' R' U R r' U r",
+    "R U R' U' M' U R U' r'",
+];
+
+pub const PLL_CASES: [(&str, &str); 21] = [
+    ("Aa", "x R' U R' D2 R U' R' D2 R2 x'"),
+    ("Ab", "x R2 D2 R U R' D2 R U' R x'"),
+    ("E",  "x' R U' R' D R U R' D' R U R' D R U' R' D' x"),
+    ("F",  "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    ("Ga", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    ("Gb", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    ("Gc", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    ("Gd", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    ("H",  "M2 U M2 U2 M2 U M2"),
+    ("Ja", "x R2 F R F' R U2 r' U r U2 x'"),
+    ("Jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    ("Na", "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'"),
+    ("Nb", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    ("Ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("Rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    ("T",  "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("Ua", "M2 U M U2 M' U M2"),
+    ("Ub", "M2 U' M U2 M' U' M2"),
+    ("V",  "R' U R' U' R D' R' D R' U D' R2 U' R2 D R2"),
+    ("Y",  "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("Z",  "M' U M2 U M2 U M' U2 M2"),
+];
+
+// Algorithm for each F2L case of the front right pair (the corner and edge anywhere in the U layer or the FR slot),
+// made from the usual R U R' and F' U F triggers. Each case only appears once however the U face is turned
+pub const F2L_CASES: [&str; 41] = [
+    "F' U' F",
+    "F' U2 F",
+    "R U R'",
+    "R U2 R'",
+    "F' U F R U R'",
+    "F' U' F R U R'",
+    "F' U2 F R U R'",
+    "F' U2 F R U2 R'",
+    "F' U2 F2 R' F' R",
+    "R U R' F' U' F",
+    "R U' R' F' U' F",
+    "R U2 R' F' U2 F",
+    "R U2 R2 F R F'",
+    "F R' F' R F' U' F",
+    "F' U F U' F' U' F",
+    "F' U F U2 R U R'",
+    "F' U' F U R U' R'",
+    "F' U' F U' F' U' F",
+    "F' U' F U2 F' U F",
+    "F' U2 F U F' U' F",
+    "F' U2 F U' F' U' F",
+    "F' U2 F U' R U R'",
+    "F' U2 F U2 F' U F",
+    "F' U2 F U2 R U R'",
+    "R U R' U2 R U' R'",
+    "R U' R' U R U R'",
+    "R U' R' U' F' U' F",
+    "R U' R' U' R U R'",
+    "R U' R' U2 F' U' F",
+    "R U2 R' U F' U' F",
+    "R U2 R' U R U R'",
+    "R U2 R' U' R U R'",
+    "R U2 R' U2 R U' R'",
+    "F R' F' R U R U R'",
+    "R' F R F' U F' U' F",
+    "R' F R F' U' F' U' F",
+    "F R' F' R2 U2 R' U' R U R'",
+    "F' U F U2 F' U' F R U R'",
+    "R U' R' U2 R U R' F' U' F",
+    "R' F R F2 U2 F U F' U' F",
+    "F' U F U' R U2 R' U2 R U' R'",
+];
+
+// ZBLL case shapes (which pieces are oriented), as the OCLL case in OLL_CASES which orients them
+const ZBLL_SHAPES: [(&str, usize); 7] = [("H", 20), ("Pi", 21), ("U", 22), ("T", 23), ("L", 24), ("AS", 25), ("S", 26)];
+
+// A last layer case, along with the U turns done before and after its algorithm was undone to make it
+struct Entry {
+    stage: Stage,
+    case: usize,
+    before: i32,
+    after: i32,
+    pattern: Vec<u8>,
+}
+
+pub struct Recognition {
+    pub stage: Stage,
+    pub name: String,
+    pub algorithm: Vec<Move>, // Solves the case from how the cube is held (ie. without rotating it)
+}
+
+impl Recognition {
+
+    // Method to build the plain JS object given to JS (see CaseRecognition)
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: &str| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &JsValue::from_str(value)).ok(); };
+
+        set("stage", self.stage.name());
+        set("name", &self.name);
+        set("algorithm", &format_moves(&self.algorithm));
+
+        object.into()
+    }
+}
+
+/*
+    Function to recognise the last layer case of a state. Returns None unless F2L is solved
+*/
+pub fn recognise(state: &CubeState) -> Option<Recognition>
+{
+    //Find a way to hold the cube with F2L solved on the bottom
+    let (rotations, held) = holds().into_iter()
+        .map(|rotations| (rotations.clone(), state.apply_moves(&rotations)))
+        .find(|(_, held)| f2l_solved(held))?;
+
+    //Moves found while holding the cube are re-targeted so they can be done without rotating it
+    let unrotate = |moves: Vec<Move>| moves.into_iter().map(|m| rotate_move(m, &rotations)).collect::<Vec<_>>();
+
+    let u = |quarterTurns: i32| Move::new(Layers::Face(Face::U), quarterTurns);
+
+    //Solved (but for the last layer maybe needing a U turn)
+    if let Some(turns) = (0..4).find(|turns| held.apply_moves(&[u(*turns)]).is_solved(false)) {
+        let algorithm = if turns == 0 { Vec::new() } else { unrotate(vec![u(turns)]) };
+        return Some(Recognition { stage: Stage::Solved, name: "Solved".to_string(), algorithm });
+    }
+
+    let stage = if oriented(&held) { Stage::Pll } else { Stage::Oll };
+    let pattern = pattern(&held, stage);
+
+    let entry = cases().iter()
+        .filter(|entry| entry.stage == stage && entry.pattern == pattern)
+        .min_by_key(|entry| (entry.before != 0) as u8 + (entry.after != 0) as u8)?;
+
+    let (name, algorithm) = match stage {
+        Stage::Oll => (format!("OLL {}", entry.case + 1), OLL_CASES[entry.case]),
+        _          => (format!("{} Perm", PLL_CASES[entry.case].0), PLL_CASES[entry.case].1),
+    };
+
+    //Undo the U turn made after undoing the algorithm, do the algorithm, then undo the U turn made before it
+    let mut moves: Vec<Move> = [u(-entry.after)].into_iter().filter(|m| m.quarterTurns != 0).collect();
+    moves.extend(Algorithm::parse(algorithm).ok()?.expand());
+    moves.extend([u(-entry.before)].into_iter().filter(|m| m.quarterTurns != 0));
+
+    Some(Recognition { stage, name, algorithm: unrotate(moves) })
+}
+
+// Get every case, made from the solved cube the first time it is needed
+fn cases() -> &'static [Entry]
+{
+    static CASES: OnceLock<Vec<Entry>> = OnceLock::new();
+
+    CASES.get_or_init(|| {
+
+        let algorithms = OLL_CASES.iter().map(|algorithm| (Stage::Oll, *algorithm))
+            .chain(PLL_CASES.iter().map(|(_, algorithm)| (Stage::Pll, *algorithm)));
+
+        let mut entries = Vec::new();
+
+        for (index, (stage, algorithm)) in algorithms.enumerate() {
+
+            let case = if stage == Stage::Oll { index } else { index - OLL_CASES.len() };
+            let Ok(algorithm) = Algorithm::parse(algorithm) else { continue; };
+            let undo = algorithm.inverse().expand();
+
+            //U turns before only change which pieces are where, which OLL doesn't care about
+            let befores = if stage == Stage::Oll { 0..1 } else { 0..4 };
+
+            for before in befores {
+                for after in 0..4 {
+
+                    let mut moves = vec![Move::new(Layers::Face(Face::U), before)];
+                    moves.extend_from_slice(&undo);
+                    moves.push(Move::new(Layers::Face(Face::U), after));
+
+                    let state = CubeState::solved().apply_moves(&moves);
+                    entries.push(Entry { stage, case, before, after, pattern: pattern(&state, stage) });
+                }
+            }
+        }
+
+        entries
+    })
+}
+
+// Get a name and algorithm for every ZBLL case (the last layer edges already oriented), made the first time it is needed.
+// Rather than a table of one look algorithms, each is the OCLL algorithm for its shape, a U turn and a PLL algorithm
+pub fn zbll_cases() -> &'static [(String, String)]
+{
+    static CASES: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+    CASES.get_or_init(|| {
+
+        let u = |quarterTurns: i32| Move::new(Layers::Face(Face::U), quarterTurns);
+        let mut seen: Vec<Vec<u8>> = Vec::new();
+        let mut cases = Vec::new();
+
+        for (shape, oll) in ZBLL_SHAPES {
+            let mut count = 0;
+
+            for pll in [""].into_iter().chain(PLL_CASES.iter().map(|(_, algorithm)| *algorithm)) {
+                for turns in 0..4 {
+
+                    let algorithm = [OLL_CASES[oll], ["", "U", "U2", "U'"][turns], pll].into_iter()
+                        .filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" ");
+                    let Ok(parsed) = Algorithm::parse(&algorithm) else { continue; };
+                    let undo = parsed.inverse().expand();
+
+                    //Cases are the same if they only differ by U turns before or after
+                    let pattern = (0..4).flat_map(|before| (0..4).map(move |after| (before, after)))
+                        .map(|(before, after)| {
+                            let setup = [vec![u(before)], undo.clone(), vec![u(after)]].concat();
+                            pattern(&CubeState::solved().apply_moves(&setup), Stage::Pll)
+                        })
+                        .min()
+                        .unwrap_or_default();
+
+                    if seen.contains(&pattern) { continue; }
+                    seen.push(pattern);
+
+                    count += 1;
+                    cases.push((format!("ZBLL {} {}", shape, count), algorithm));
+                }
+            }
+        }
+
+        cases
+    })
+}
+
+// Get the 24 ways to hold the cube as rotations (any face on top, then turned around it)
+fn holds() -> Vec<Vec<Move>>
+{
+    let rotation = |axis: Axis, quarterTurns: i32| Move::new(Layers::Rotation(axis), quarterTurns);
+    let tops = [vec![], vec![rotation(Axis::X, 1)], vec![rotation(Axis::X, 2)], vec![rotation(Axis::X, 3)], vec![rotation(Axis::Z, 1)], vec![rotation(Axis::Z, 3)]];
+
+    tops.iter()
+        .flat_map(|top| (0..4).map(move |turns| [top.clone(), vec![rotation(Axis::Y, turns)]].concat()))
+        .map(|moves| moves.into_iter().filter(|m| m.quarterTurns != 0).collect())
+        .collect()
+}
+
+// Check the D face and the bottom two rows of the side faces match their centers
+fn f2l_solved(state: &CubeState) -> bool
+{
+    let matches = |face: Face, slots: std::ops::Range<usize>| slots.into_iter().all(|i| relative_colour(state, face.index() * 9 + i) == face);
+
+    matches(Face::D, 0..9) && [Face::F, Face::R, Face::B, Face::L].into_iter().all(|face| matches(face, 3..9))
+}
+
+fn oriented(state: &CubeState) -> bool
+{
+    (0..9).all(|i| relative_colour(state, Face::U.index() * 9 + i) == Face::U)
+}
+
+// Get the slots of the last layer stickers (the U face and top row of each side)
+fn pattern_slots() -> impl Iterator<Item = usize>
+{
+    (0..9).chain([Face::F, Face::R, Face::B, Face::L].into_iter().flat_map(|face| (0..3).map(move |i| face.index() * 9 + i)))
+}
+
+// Get the last layer stickers (the U face and top row of each side): for OLL only which are the U colour, for PLL their colours
+fn pattern(state: &CubeState, stage: Stage) -> Vec<u8>
+{
+    pattern_slots().map(|slot| match stage {
+        Stage::Oll => (relative_colour(state, slot) == Face::U) as u8,
+        _          => relative_colour(state, slot).index() as u8,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<(Stage, String, &'static str)>
+    {
+        let oll = OLL_CASES.iter().enumerate().map(|(i, algorithm)| (Stage::Oll, format!("OLL {}", i + 1), *algorithm));
+        let pll = PLL_CASES.iter().map(|(name, algorithm)| (Stage::Pll, format!("{} Perm", name), *algorithm));
+        oll.chain(pll).collect()
+    }
+
+    #[test]
+    fn case_names_are_distinct()
+    {
+        let names = names();
+        for (i, (_, name, _)) in names.iter().enumerate() {
+            assert!(names[i + 1..].iter().all(|(_, other, _)| other != name), "{} is listed twice", name);
+        }
+    }
+
+    #[test]
+    fn cases_look_different_however_the_u_face_is_turned()
+    {
+        let entries = cases();
+        assert_eq!(entries.iter().filter(|entry| entry.stage == Stage::Oll).count(), 57 * 4);
+        assert_eq!(entries.iter().filter(|entry| entry.stage == Stage::Pll).count(), 21 * 16);
+
+        for entry in entries {
+            let same = entries.iter().find(|other| other.stage == entry.stage && other.case != entry.case && other.pattern == entry.pattern);
+            assert!(same.is_none(), "{:?} cases {} and {} look the same", entry.stage, entry.case, same.map_or(0, |other| other.case));
+        }
+    }
+
+    #[test]
+    fn algorithms_only_change_the_last_layer()
+    {
+        for (stage, name, algorithm) in names() {
+            let done = CubeState::solved().apply_moves(&Algorithm::parse(algorithm).unwrap().expand());
+
+            assert!(f2l_solved(&done), "{} breaks F2L", name);
+            assert!(!done.is_solved(false), "{} does nothing", name);
+            assert_eq!(oriented(&done), stage == Stage::Pll, "{} orients the wrong pieces", name);
+        }
+    }
+
+    #[test]
+    fn f2l_cases_only_change_the_front_right_pair_and_last_layer()
+    {
+        let u = |quarterTurns: i32| Move::new(Layers::Face(Face::U), quarterTurns);
+        let pair = [Face::F.index() * 9 + 5, Face::F.index() * 9 + 8, Face::R.index() * 9 + 3, Face::R.index() * 9 + 6, Face::D.index() * 9 + 2];
+        let mut seen: Vec<Vec<u8>> = Vec::new();
+
+        for (i, algorithm) in F2L_CASES.iter().enumerate() {
+            let setup = CubeState::solved().apply_moves(&Algorithm::parse(algorithm).unwrap().inverse().expand());
+            let untouched = (0..54).filter(|slot| !pair.contains(slot) && !pattern_slots().any(|top| top == *slot));
+
+            assert!(untouched.into_iter().all(|slot| setup.sticker(slot) == slot), "F2L {} breaks more than its pair", i + 1);
+            assert!(!f2l_solved(&setup), "F2L {} is already solved", i + 1);
+
+            //Where the pair's stickers are, however the U face is turned
+            let placed = (0..4).map(|turns| {
+                let state = setup.apply_moves(&[u(turns)]);
+                pair.iter().map(|home| (0..54).find(|slot| state.sticker(*slot) == *home).unwrap() as u8).collect::<Vec<_>>()
+            }).min().unwrap();
+
+            assert!(!seen.contains(&placed), "F2L {} is listed twice", i + 1);
+            seen.push(placed);
+        }
+    }
+
+    #[test]
+    fn zbll_cases_are_each_solved_by_their_algorithm()
+    {
+        let cases = zbll_cases();
+        assert_eq!(cases.len(), 472);
+
+        for (shape, _) in ZBLL_SHAPES {
+            let count = cases.iter().filter(|(name, _)| name.starts_with(&format!("ZBLL {} ", shape))).count();
+            assert_eq!(count, if shape == "H" { 40 } else { 72 }, "{}", shape);
+        }
+
+        for (name, algorithm) in cases {
+            let setup = CubeState::solved().apply_moves(&Algorithm::parse(algorithm).unwrap().inverse().expand());
+
+            assert!(f2l_solved(&setup) && !oriented(&setup), "{} isn't a ZBLL case", name);
+            assert!((0..9).filter(|i| i % 2 == 1).all(|i| relative_colour(&setup, Face::U.index() * 9 + i) == Face::U), "{} flips edges", name);
+        }
+    }
+
+    #[test]
+    fn recognised_under_any_auf_and_rotation()
+    {
+        let u = |quarterTurns: i32| Move::new(Layers::Face(Face::U), quarterTurns);
+        let rotations: Vec<Vec<Move>> = (0..4).map(|turns| vec![Move::new(Layers::Rotation(Axis::Y), turns)])
+            .chain([vec![Move::new(Layers::Rotation(Axis::X), 2)], vec![Move::new(Layers::Rotation(Axis::Z), 1)]])
+            .collect();
+
+        for (stage, name, algorithm) in names() {
+            let undo = Algorithm::parse(algorithm).unwrap().inverse().expand();
+
+            //Every U turn before and after held the usual way up, then every rotation with one of them
+            let setups = (0..4).flat_map(|before| (0..4).map(move |after| (before, after, 0)))
+                .chain((0..rotations.len()).map(|rotation| (1, 3, rotation)));
+
+            for (before, after, rotation) in setups {
+                let setup = [vec![u(before)], undo.clone(), vec![u(after)], rotations[rotation].clone()].concat();
+                let state = CubeState::solved().apply_moves(&setup);
+
+                let recognition = recognise(&state).unwrap_or_else(|| panic!("{} not recognised", name));
+                assert_eq!(recognition.stage, stage);
+                assert_eq!(recognition.name, name);
+
+                //The algorithm given solves PLL, and leaves OLL cases oriented (ready for PLL)
+                let done = state.apply_moves(&recognition.algorithm);
+                match stage {
+                    Stage::Oll => assert!(recognise(&done).is_some_and(|next| next.stage != Stage::Oll), "{} not oriented", name),
+                    _          => assert!(done.is_solved(false), "{} not solved", name),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::RubixError;
+use crate::history::History;
+use crate::notation::{format_moves, Algorithm};
+use crate::state::CubeState;
+
+/*
+    Reconstructions (a setup, usually the scramble, and the solution) shared as links in the alg.cubing.net format,
+    which twizzle (cubing.net) links can also be read from. alg.cubing.net writes spaces as "_" and primes as "-"
+    in its query parameters, while twizzle uses plain url encoding and calls the setup "setup-alg"
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const RECONSTRUCTION: &'static str = r#"
+/** Setup (eg. the scramble) and solution of a reconstruction, in standard notation */
+export interface Reconstruction {
+    setup: string;
+    alg: string;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Reconstruction")]
+    pub type ReconstructionObject;
+}
+
+// Page reconstruction links are made to
+const ALG_CUBING_NET: &str = "https://alg.cubing.net/";
+
+/*
+ * Make an alg.cubing.net link to a reconstruction. Throws a RubixError if either algorithm is invalid
+ */
+#[wasm_bindgen(js_name = reconstructionUrl)]
+pub fn reconstructionUrl(setup: &str, alg: &str) -> Result<String, JsValue>
+{
+    Ok(Reconstruction::new(setup, alg)?.to_url())
+}
+
+/*
+ * Read the setup and solution from an alg.cubing.net or twizzle link (or just its query string eg. "?setup=R_U&alg=U-_R-").
+ * Throws a RubixError if either algorithm is invalid
+ */
+#[wasm_bindgen(js_name = parseReconstructionUrl)]
+pub fn parseReconstructionUrl(url: &str) -> Result<ReconstructionObject, JsValue>
+{
+    Ok(Reconstruction::from_url(url)?.to_js().unchecked_into())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reconstruction {
+    pub setup: String,
+    pub alg: String,
+}
+
+impl Reconstruction {
+
+    pub fn new(setup: &str, alg: &str) -> Result<Self, RubixError>
+    {
+        Algorithm::parse(setup)?;
+        Algorithm::parse(alg)?;
+
+        Ok(Self { setup: setup.trim().to_string(), alg: alg.trim().to_string() })
+    }
+
+    /*
+        Reconstruction of the moves in a history, set up by its scramble. Gives a RubixError if the scramble doesn't set up
+        the state the moves were made from (the cube was set to a state, or scrambled when it wasn't solved)
+    */
+    pub fn of(history: &History) -> Result<Self, RubixError>
+    {
+        if CubeState::solved().apply_moves(&history.scramble).to_facelets() != history.start.to_facelets() {
+            return Err(RubixError::InvalidState("the cube wasn't scrambled from solved, so its scramble can't set it up".to_string()));
+        }
+
+        Ok(Self { setup: format_moves(&history.scramble), alg: format_moves(&history.solution()) })
+    }
+
+    pub fn to_url(&self) -> String
+    {
+        format!("{}?setup={}&alg={}&type=reconstruction", ALG_CUBING_NET, encode(&self.setup), encode(&self.alg))
+    }
+
+    pub fn from_url(url: &str) -> Result<Self, RubixError>
+    {
+        //Only the query string is needed, without any fragment after it
+        let query = url.split('#').next().unwrap_or_default();
+        let query = query.split_once('?').map_or(query, |(_, query)| query);
+
+        let mut reconstruction = Self::default();
+
+        for (key, value) in query.split('&').filter_map(|parameter| parameter.split_once('=')) {
+            match key {
+                "setup" | "setup-alg" => reconstruction.setup = decode(value)?,
+                "alg"                 => reconstruction.alg = decode(value)?,
+                _ => (),
+            }
+        }
+
+        Self::new(&reconstruction.setup, &reconstruction.alg)
+    }
+
+    // Method to build the plain JS object given to JS (see Reconstruction)
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: &str| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &JsValue::from_str(value)).ok(); };
+
+        set("setup", &self.setup);
+        set("alg", &self.alg);
+
+        object.into()
+    }
+}
+
+// Write an algorithm as alg.cubing.net does, with anything else which isn't safe in a url percent encoded
+fn encode(algorithm: &str) -> String
+{
+    let mut encoded = String::new();
+
+    for c in algorithm.split_whitespace().collect::<Vec<_>>().join(" ").chars() {
+        match c {
+            ' '  => encoded.push('_'),
+            '\'' => encoded.push('-'),
+            c if c.is_ascii_alphanumeric() || "()[],:".contains(c) => encoded.push(c),
+            c => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() { encoded.push_str(&format!("%{:02X}", byte)); }
+            }
+        }
+    }
+
+    encoded
+}
+
+/*
+    Read an algorithm from a query parameter written by alg.cubing.net or twizzle, dropping any comments.
+    Percent escapes which are cut short, aren't hex or aren't UTF-8 are an error, so a mangled link isn't read as another algorithm
+*/
+fn decode(value: &str) -> Result<String, RubixError>
+{
+    let malformed = || RubixError::InvalidAlgorithm(format!("Malformed percent escape in \"{}\"", value));
+
+    let mut bytes = Vec::new();
+    let mut chars = value.bytes();
+
+    while let Some(byte) = chars.next() {
+        let hex = |c: Option<u8>| c.and_then(|c| (c as char).to_digit(16));
+
+        match byte {
+            b'+' | b'_' => bytes.push(b' '),
+            b'-'        => bytes.push(b'\''),
+            b'%'        => {
+                let (Some(high), Some(low)) = (hex(chars.next()), hex(chars.next())) else { return Err(malformed()); };
+                bytes.push((high * 16 + low) as u8);
+            },
+            byte => bytes.push(byte),
+        }
+    }
+
+    let decoded = String::from_utf8(bytes).map_err(|_| malformed())?;
+
+    Ok(decoded.lines()
+        .map(|line| line.split("//").next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('.', " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_moves;
+
+    #[test]
+    fn encode_decode_round_trip()
+    {
+        for algorithm in ["R U R' U'", "R2 U2' F", "[R U R', D]2", "(R U R' U')3 [r: U2] x'", "M' E2 S", ""] {
+            assert_eq!(decode(&encode(algorithm)).unwrap(), algorithm);
+        }
+
+        assert_eq!(encode("R' U2 [R, D]"), "R-_U2_[R,_D]");
+    }
+
+    #[test]
+    fn links_round_trip()
+    {
+        let reconstruction = Reconstruction::new("F R' (U2 L)2", "[R' U' R, D'] y x2").unwrap();
+        let url = reconstruction.to_url();
+
+        assert!(url.starts_with(ALG_CUBING_NET));
+        assert_eq!(Reconstruction::from_url(&url).unwrap(), reconstruction);
+    }
+
+    #[test]
+    fn histories_must_be_scrambled_from_solved()
+    {
+        let scramble = parse_moves("R U F'").unwrap();
+        let mut history = History::default();
+
+        history.restart(&CubeState::solved().apply_moves(&scramble), &scramble, false);
+        history.record(parse_moves("F").unwrap()[0], 1.0, false);
+        assert_eq!(Reconstruction::of(&history).unwrap(), Reconstruction { setup: "R U F'".to_string(), alg: "F".to_string() });
+
+        //Set to a state, or scrambled on top of another scramble
+        history.restart(&CubeState::solved().apply_moves(&scramble), &[], false);
+        assert!(Reconstruction::of(&history).is_err());
+
+        history.restart(&CubeState::solved().apply_moves(&parse_moves("D2 R U F'").unwrap()), &scramble, false);
+        assert!(Reconstruction::of(&history).is_err());
+    }
+
+    #[test]
+    fn reads_twizzle_and_alg_cubing_net_escapes()
+    {
+        assert_eq!(decode("R%27+U2%27%20%5BR%2C+U%5D").unwrap(), "R' U2' [R, U]");
+        assert_eq!(decode("R_U-_//_comment%0AF").unwrap(), "R U'  F");
+
+        let reconstruction = Reconstruction::from_url("https://alpha.twizzle.net/edit/?setup-alg=R+U&alg=U%27+R%27#top").unwrap();
+        assert_eq!(reconstruction, Reconstruction { setup: "R U".to_string(), alg: "U' R'".to_string() });
+    }
+
+    #[test]
+    fn rejects_malformed_escapes()
+    {
+        for value in ["R%", "R%2", "R%ZZ_U", "%G7", "%FF"] {
+            assert!(decode(value).is_err(), "{}", value);
+        }
+
+        assert!(Reconstruction::from_url("?setup=R_U&alg=U%2-R").is_err());
+    }
+}
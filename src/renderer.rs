@@ -0,0 +1,195 @@
+
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlTexture, WebGlUniformLocation, WebGlVertexArrayObject};
+use webgl_matrix::{ProjectionMatrix, Mat4};
+
+use crate::error::RubixError;
+use crate::cube::{Cube, CubieStyle, Geometry, INSTANCE_FLOATS};
+use crate::texture::AtlasLayout;
+
+//Perspective projection settings
+const FIELD_OF_VIEW: f32 = 45.0 * std::f32::consts::PI / 180.0;
+const Z_NEAR: f32 = 0.1;
+const Z_FAR: f32 = 100.0;
+
+// Vertex array for the shared cubie geometry along with how many vertices to draw from it
+struct Mesh {
+    VAO: WebGlVertexArrayObject,
+    buffers: Vec<WebGlBuffer>, // One per vertex attribute, kept so they can be deleted along with the VAO
+    numberOfVertices: i32,
+}
+
+/*
+    Owner of the GPU side objects used to draw every cubie of a rubik's cube with a single instanced draw call
+*/
+pub struct CubieRenderer {
+    shader_program: WebGlProgram,
+    texture: WebGlTexture,
+    flat: Mesh,
+    bevelled: Mesh,
+    instanceBuffer: WebGlBuffer, // Model matrix and sprite sheet tiles of every cubie, rewritten every frame
+    lighting_location: Option<WebGlUniformLocation>,
+    hidden_location: Option<WebGlUniformLocation>,
+    projection_location: Option<WebGlUniformLocation>,
+}
+
+impl CubieRenderer {
+
+    pub fn new(gl: &WebGl2RenderingContext, shader_program: &WebGlProgram, texture: WebGlTexture, layout: AtlasLayout) -> Result<Self, RubixError>
+    {
+        let instanceBuffer = gl.create_buffer().ok_or(RubixError::GlObject("buffer"))?;
+
+        let flat = Self::create_mesh(gl, shader_program, &Cube::get_flat_geometry(), &instanceBuffer)?;
+        let bevelled = Self::create_mesh(gl, shader_program, &Cube::get_bevelled_geometry(), &instanceBuffer)?;
+
+        //Size of the sprite sheet so the shader can find the tile for each side
+        let (cols, rows) = layout.size();
+        gl.uniform2f(gl.get_uniform_location(shader_program, "u_atlasSize").as_ref(), cols as f32, rows as f32);
+
+        //Set directional light (shining down and away from the camera) and ambient light for the lit cubie style
+        gl.uniform3f(gl.get_uniform_location(shader_program, "u_lightDirection").as_ref(), -0.4, -0.7, -0.6);
+        gl.uniform1f(gl.get_uniform_location(shader_program, "u_ambient").as_ref(), 0.35);
+
+        Ok(Self {
+            shader_program: shader_program.clone(),
+            texture,
+            flat,
+            bevelled,
+            instanceBuffer,
+            lighting_location: gl.get_uniform_location(shader_program, "u_lighting"),
+            hidden_location: gl.get_uniform_location(shader_program, "u_hidden"),
+            projection_location: gl.get_uniform_location(shader_program, "u_projection"),
+        })
+    }
+
+    // Helper function to upload the shared vertex data to the GPU and point the per instance attributes at the instance buffer
+    fn create_mesh(gl: &WebGl2RenderingContext, shader_program: &WebGlProgram, geometry: &Geometry, instanceBuffer: &WebGlBuffer) -> Result<Mesh, RubixError>
+    {
+        //Create VAO for cubie geometry
+        let vao = gl.create_vertex_array().ok_or(RubixError::GlObject("vertex array"))?;
+
+        //Bind VAO to current webGL context
+        gl.bind_vertex_array(Some(&vao));
+
+        let buffers = vec![
+            Self::upload_attribute(gl, shader_program, "a_coords", &geometry.coordinates, 3)?,
+            Self::upload_attribute(gl, shader_program, "a_normal", &geometry.normals, 3)?,
+            Self::upload_attribute(gl, shader_program, "a_faceCoord", &geometry.faceCoords, 2)?,
+            Self::upload_attribute(gl, shader_program, "a_side", &geometry.sides, 1)?,
+            Self::upload_attribute(gl, shader_program, "a_sticker", &geometry.stickers, 1)?,
+        ];
+
+        // INSTANCE DATA (interleaved, advancing once per cubie rather than once per vertex)
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(instanceBuffer));
+        let stride = (INSTANCE_FLOATS * 4) as i32; // 4 bytes per float
+
+        //A mat4 attribute takes up 4 consecutive locations, one per column
+        let model_location = gl.get_attrib_location(shader_program, "a_model");
+        if model_location >= 0 {
+            for column in 0..4 {
+                Self::instance_attribute(gl, model_location as u32 + column, 4, stride, column as i32 * 16);
+            }
+        }
+
+        //Tiles for the first 3 sides then the last 3 sides
+        for (name, offset) in [("a_tilesA", 64), ("a_tilesB", 76)] {
+            let location = gl.get_attrib_location(shader_program, name);
+            if location >= 0 {
+                Self::instance_attribute(gl, location as u32, 3, stride, offset);
+            }
+        }
+
+        Ok(Mesh {
+            VAO: vao,
+            buffers,
+            numberOfVertices: (geometry.coordinates.len() / 3) as i32,
+        })
+    }
+
+    // Helper function to put an array of vertex data into a buffer of the currently bound VAO
+    fn upload_attribute(gl: &WebGl2RenderingContext, shader_program: &WebGlProgram, name: &str, data: &[f32], size: i32) -> Result<WebGlBuffer, RubixError>
+    {
+        // Note that `Float32Array::view` is somewhat dangerous (hence the `unsafe`!). This is creating a raw view into our
+        // module's `WebAssembly.Memory` buffer, but if we allocate more pages for ourself (aka do a memory allocation in Rust) 
+        // it'll cause the buffer to change, causing the `Float32Array` to be invalid. As a result, 
+        // after `Float32Array::view` we have to be very careful not to do any memory allocations before it's dropped.
+        let buffer = gl.create_buffer().ok_or(RubixError::GlObject("buffer"))?;
+        let array = unsafe { js_sys::Float32Array::view(data) };
+
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
+        gl.buffer_data_with_array_buffer_view( WebGl2RenderingContext::ARRAY_BUFFER, &array, WebGl2RenderingContext::STATIC_DRAW);
+
+        //Attributes optimised away by the shader compiler have no location
+        let location = gl.get_attrib_location(shader_program, name);
+        if location < 0 { return Ok(buffer); }
+
+        gl.vertex_attrib_pointer_with_i32( location as u32, size, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.enable_vertex_attrib_array(location as u32);
+
+        Ok(buffer)
+    }
+
+    // Helper function to point an attribute at part of the currently bound instance buffer
+    fn instance_attribute(gl: &WebGl2RenderingContext, location: u32, size: i32, stride: i32, offset: i32)
+    {
+        gl.vertex_attrib_pointer_with_i32( location, size, WebGl2RenderingContext::FLOAT, false, stride, offset);
+        gl.enable_vertex_attrib_array(location);
+        gl.vertex_attrib_divisor(location, 1);
+    }
+
+    // Method to match the viewport and projection matrix to a new size (in device pixels) of the canvas drawing buffer
+    pub fn resize(&self, gl: &WebGl2RenderingContext, width: u32, height: u32)
+    {
+        gl.viewport(0, 0, width as i32, height as i32);
+
+        //Aspect ratio follows the canvas so the cube is never stretched
+        let aspectRatio = width as f32 / height.max(1) as f32;
+        let projection_matrix = Mat4::create_perspective(FIELD_OF_VIEW, aspectRatio, Z_NEAR, Z_FAR);
+
+        //Set shader program uniform for the shader program
+        gl.use_program(Some(&self.shader_program));
+        gl.uniform_matrix4fv_with_f32_array(self.projection_location.as_ref(), false, &projection_matrix);
+    }
+
+    // Method to draw every cubie at once given their instance data (see Cube::push_instance_data), greying out the stickers if hidden
+    pub fn draw(&self, gl: &WebGl2RenderingContext, instanceData: &[f32], style: CubieStyle, hidden: bool)
+    {
+        let mesh = match style {
+            CubieStyle::Flat     => &self.flat,
+            CubieStyle::Bevelled => &self.bevelled,
+        };
+
+        //Bind shader to current webGL context
+        gl.use_program(Some(&self.shader_program));
+
+        //Only bevelled cubies are lit, flat ones keep their plain colours
+        gl.uniform1i(self.lighting_location.as_ref(), (style == CubieStyle::Bevelled) as i32);
+        gl.uniform1i(self.hidden_location.as_ref(), hidden as i32);
+
+        //Upload this frame's instance data (see note on Float32Array::view above)
+        let array = unsafe { js_sys::Float32Array::view(instanceData) };
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.instanceBuffer));
+        gl.buffer_data_with_array_buffer_view( WebGl2RenderingContext::ARRAY_BUFFER, &array, WebGl2RenderingContext::DYNAMIC_DRAW);
+
+        //Bind the shared VAO (for the style being drawn) to current webGL context
+        gl.bind_vertex_array(Some(&mesh.VAO));
+
+        //Make single draw call for every cubie
+        let instances = (instanceData.len() / INSTANCE_FLOATS) as i32;
+        gl.draw_arrays_instanced(WebGl2RenderingContext::TRIANGLES, 0, mesh.numberOfVertices, instances);
+    }
+
+    // Method to free everything the renderer created on the GPU (ie. when the cube is destroyed)
+    pub fn delete(self, gl: &WebGl2RenderingContext)
+    {
+        gl.bind_vertex_array(None);
+
+        for mesh in [self.flat, self.bevelled] {
+            gl.delete_vertex_array(Some(&mesh.VAO));
+            mesh.buffers.iter().for_each(|buffer| gl.delete_buffer(Some(buffer)));
+        }
+
+        gl.delete_buffer(Some(&self.instanceBuffer));
+        gl.delete_texture(Some(&self.texture));
+        gl.delete_program(Some(&self.shader_program));
+    }
+}
@@ -0,0 +1,250 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::history::History;
+use crate::notation::Move;
+use crate::state::CubeState;
+
+/*
+    Replay of recorded moves, re-animated with the timing they were made with (sped up or slowed down).
+    The replay is a timeline from the first move starting to the last finishing: what is drawn is worked out from the position
+    on the timeline, so it can be paused, scrubbed to any point or stepped a move at a time.
+    Moves made on the inverse scramble (NISS) are replayed on the inverse scramble, so the cube switches side along with them
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const REPLAY_STATUS: &'static str = r#"
+/** Where a replay is up to. Position and duration are in seconds of the recorded solve (not the replay speed) */
+export interface ReplayStatus {
+    position: number;
+    duration: number;
+    move: number;
+    moveCount: number;
+    speed: number;
+    paused: boolean;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "ReplayStatus | undefined")]
+    pub type ReplayStatus;
+}
+
+// Slowest and fastest a replay can be played at
+pub const MIN_REPLAY_SPEED: f64 = 0.25;
+pub const MAX_REPLAY_SPEED: f64 = 4.0;
+
+// Recorded move along with when it finishes on the timeline and how long it takes to animate
+#[derive(Clone, Copy, Debug)]
+struct ReplayMove {
+    turn: Move,
+    end: f64,
+    length: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Replay {
+    states: Vec<CubeState>,   // State once each number of moves has finished, seen from the side the next move was made on
+    moves: Vec<ReplayMove>,
+    pub live: CubeState,      // State of the cube before the replay started, to go back to once it stops
+    pub drawn: Option<usize>, // Moves finished in the state last drawn, so the state is only rebuilt when it changes
+    position: f64,            // Seconds along the timeline
+    speed: f64,
+    paused: bool,
+}
+
+impl Replay {
+
+    // Create a replay of a history, where a quarter turn is animated in the given time (or quicker if the next move came sooner)
+    pub fn new(history: &History, live: CubeState, quarterTurnTime: f64) -> Self
+    {
+        let length = |m: &Move| if m.quarterTurns == 2 { quarterTurnTime * 2.0 } else { quarterTurnTime };
+
+        //Moves are recorded when they finish, so the timeline starts early enough for the first one to be animated
+        let first = history.moves.first().map_or(0.0, |m| m.time - length(&m.turn));
+        let mut previous = 0.0;
+
+        let moves = history.moves.iter().map(|m| {
+            let end = m.time - first;
+            let turn = ReplayMove { turn: m.turn, end, length: length(&m.turn).min(end - previous).max(0.0) };
+            previous = end;
+            turn
+        }).collect();
+
+        //States are seen from the side the next move was made on (the last move's side once they have all finished)
+        let side = |finished: usize| history.moves.get(finished).or(history.moves.last()).is_some_and(|m| m.inverse);
+
+        let mut made = history.clone();
+        made.moves.clear();
+        let mut states = vec![made.side_state(side(0))];
+
+        //Each move is made on the state before it, which only has to be worked out again when the next move is on the other side
+        for (i, m) in history.moves.iter().enumerate() {
+            made.moves.push(*m);
+
+            let state = match side(i + 1) == m.inverse {
+                true  => states[i].apply_moves(&[m.turn]),
+                false => made.side_state(side(i + 1)),
+            };
+            states.push(state);
+        }
+
+        Self { states, moves, live, drawn: None, position: 0.0, speed: 1.0, paused: false }
+    }
+
+    pub fn duration(&self) -> f64
+    {
+        self.moves.last().map_or(0.0, |m| m.end)
+    }
+
+    // Method to move along the timeline by the time since the last frame, pausing at the end
+    pub fn advance(&mut self, delta: f64)
+    {
+        if self.paused { return; }
+
+        self.position = (self.position + delta * self.speed).min(self.duration());
+        if self.position >= self.duration() { self.paused = true; }
+    }
+
+    pub fn setSpeed(&mut self, speed: f64)
+    {
+        self.speed = speed.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED);
+    }
+
+    // Method to pause or play the replay. Playing from the end starts again from the beginning
+    pub fn setPaused(&mut self, paused: bool)
+    {
+        if !paused && self.position >= self.duration() { self.position = 0.0; }
+        self.paused = paused;
+    }
+
+    // Method to jump to a position on the timeline (in seconds)
+    pub fn seek(&mut self, position: f64)
+    {
+        self.position = position.clamp(0.0, self.duration());
+    }
+
+    // Method to pause and jump forwards (or backwards) by a number of moves, to just after a move has finished
+    pub fn step(&mut self, moves: i32)
+    {
+        let finished = self.finished() as i64 + moves as i64;
+        self.paused = true;
+
+        self.position = match finished {
+            n if n <= 0 || self.moves.is_empty() => 0.0,
+            n => self.moves[(n as usize).min(self.moves.len()) - 1].end,
+        };
+    }
+
+    // Number of moves which have finished at the current position
+    pub fn finished(&self) -> usize
+    {
+        self.moves.iter().take_while(|m| m.end <= self.position).count()
+    }
+
+    // Get the state once a number of moves have finished
+    pub fn state_after(&self, finished: usize) -> CubeState
+    {
+        self.states[finished].clone()
+    }
+
+    // Get the move being animated at the current position (if there is one) and how far through it is, from 0 to 1
+    pub fn animating(&self) -> Option<(Move, f32)>
+    {
+        let next = self.moves.get(self.finished())?;
+        let left = next.end - self.position;
+
+        (left < next.length).then(|| (next.turn, (1.0 - left / next.length) as f32))
+    }
+
+    // Method to build the plain JS object given to JS (see ReplayStatus)
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("position", JsValue::from_f64(self.position));
+        set("duration", JsValue::from_f64(self.duration()));
+        set("move", JsValue::from(self.finished() as u32));
+        set("moveCount", JsValue::from(self.moves.len() as u32));
+        set("speed", JsValue::from_f64(self.speed));
+        set("paused", JsValue::from_bool(self.paused));
+
+        object.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::TimedMove;
+    use crate::notation::parse_moves;
+    use crate::scramble::{random_scramble, seeded_random};
+
+    fn history(scramble: &str, moves: &[(&str, bool)]) -> History
+    {
+        let scramble = parse_moves(scramble).unwrap();
+        let mut history = History::default();
+        history.restart(&CubeState::solved().apply_moves(&scramble), &scramble, false);
+
+        for (i, (turn, inverse)) in moves.iter().enumerate() {
+            history.moves.push(TimedMove { turn: parse_moves(turn).unwrap()[0], time: i as f64, inverse: *inverse });
+        }
+        history
+    }
+
+    #[test]
+    fn replays_moves_from_the_start()
+    {
+        let history = history("R U F'", &[("F", false), ("U'", false), ("R'", false)]);
+        let replay = Replay::new(&history, CubeState::default(), 0.1);
+
+        assert_eq!(replay.state_after(0), history.start);
+        assert_eq!(replay.state_after(1), history.start.apply_moves(&parse_moves("F").unwrap()));
+        assert!(replay.state_after(3).is_solved(false));
+    }
+
+    #[test]
+    fn moves_on_the_inverse_scramble_are_replayed_there()
+    {
+        let moves = [("F", false), ("R", true), ("U", true), ("U'", false)];
+        let history = history("R U F' D2", &moves);
+        let replay = Replay::new(&history, CubeState::default(), 0.1);
+        assert_eq!(replay.states.len(), moves.len() + 1);
+
+        //Each move takes the state it was made on to the next state made on the same side
+        for (finished, (turn, inverse)) in moves.iter().enumerate() {
+            let mut made = history.clone();
+            made.moves.truncate(finished + 1);
+
+            assert_eq!(replay.state_after(finished).apply_moves(&parse_moves(turn).unwrap()), made.side_state(*inverse), "{}", finished);
+        }
+
+        //Rather than the inverse moves being made on the scramble
+        assert_ne!(replay.state_after(2), history.start.apply_moves(&parse_moves("F R").unwrap()));
+        assert_eq!(replay.state_after(4), history.side_state(false));
+    }
+
+    #[test]
+    fn states_match_working_each_out_from_the_start()
+    {
+        let mut random = seeded_random(45);
+        let turns = random_scramble(300, &mut random);
+
+        //Switching side every so often
+        let moves: Vec<(String, bool)> = turns.iter().enumerate().map(|(i, m)| (m.to_string(), (i / 7) % 3 == 1)).collect();
+        let moves: Vec<(&str, bool)> = moves.iter().map(|(turn, inverse)| (turn.as_str(), *inverse)).collect();
+
+        let history = history("F2 D' L", &moves);
+        let replay = Replay::new(&history, CubeState::default(), 0.1);
+
+        for finished in (0..=moves.len()).step_by(13).chain([moves.len()]) {
+            let mut made = history.clone();
+            made.moves.truncate(finished);
+            let inverse = history.moves.get(finished).or(history.moves.last()).is_some_and(|m| m.inverse);
+
+            assert_eq!(replay.state_after(finished), made.side_state(inverse), "{}", finished);
+        }
+    }
+}
@@ -1,8 +1,17 @@
 
-use crate::cube::Cube;
-use std::{cell::RefCell, f32::consts, array};   
-
-use web_sys::{WebGl2RenderingContext, WebGlProgram,  WebGlUniformLocation};
+use crate::cube::{Cube, CubieStyle, INSTANCE_FLOATS};
+use crate::events::CubeEvent;
+use crate::fmc::FmcTimer;
+use crate::history::{History, SolveRecord};
+use crate::progress::cross_progress;
+use crate::notation::{Layers, Move};
+use crate::replay::Replay;
+use crate::renderer::CubieRenderer;
+use crate::state::{dot, CubeState, Face, IVec3};
+use crate::texture::AtlasLayout;
+use std::{cell::RefCell, collections::VecDeque, f32::consts};   
+
+use web_sys::WebGl2RenderingContext;
 use webgl_matrix::{Matrix, Mat4, Vec3};
 
 const CUBE_ROTATION_SPEED: f32 = 0.15; // Arbitrary
@@ -12,9 +21,30 @@ pub struct Rubix
 {
     cubePosition: Vec3,    
     cubeRotation: [f32; 3], //Maybe try making this quaternions
-    cubes: Vec<Cube>, 
-    rotatingFace: Option<RotationFace>,
+    cubes: Vec<Cube>,       //Indexed by the home position of each cubie
+    state: CubeState,       //Logical state which the cubies are drawn from
+    supercube: bool,        //If true then centers must also be upright for the cube to be solved
+    style: CubieStyle,
+    animation: Option<Animation>, //Move currently being animated
+    queue: VecDeque<Move>,        //Moves waiting to be animated
+    movesQueued: u64,             //Count of every move ever queued, so callers can wait for a move to finish
+    movesFinished: u64,
+    turnSpeed: f32,               //Radians per second that layers turn at
     rotationDirection: RotationDirection,
+    events: Vec<CubeEvent>, //Events waiting to be sent to JS
+    history: History,       //Moves finished since the cube was last scrambled
+    solves: Vec<SolveRecord>,
+    replay: Option<Replay>, //Recorded moves being replayed instead of the cube's own moves
+    blindfold: Blindfold,
+    fmc: Option<FmcTimer>,  //Time limit of the FMC attempt in progress
+}
+
+// Stage of a blindfolded solve: stickers are shown while inspecting (memorising the cube) then hidden until it is solved
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Blindfold {
+    Off,
+    Inspecting { until: Option<f64> }, // Time (in seconds, see get_current_time) inspection ends at, if it is limited
+    On,
 }
 
 #[derive(Clone, Copy)] //Clone needed for Copy, Copy needed for rotateFace method
@@ -25,109 +55,323 @@ pub enum RotationDirection {
 
 impl Rubix {
 
-    pub fn new(gl: &WebGl2RenderingContext, shader_program: &WebGlProgram, position: Vec3) -> Self
+    pub fn new(position: Vec3, layout: AtlasLayout) -> Self
     {
         let mut cubes = Vec::new();
 
         //Create rubix cube
-        for i in (0..27).into_iter() { //Magik const alert. 27 is number of cubes
+        for i in 0..27 { //Magik const alert. 27 is number of cubes
 
             //Cubie position within rubix cube // -1 since cubies are at origin and we want rubik's center to be at origin
-            let cube_pos = [(i%3) -1, ((i/3)%3) -1, (i/9) -1, 1].map(|x| x as f32);  
-            
-            //Set colour of each cubes side (back, front, left, right, bottom, top)
-            let sideColour = [0,1,3,4,5,2]; 
-            // Red 0, Orange 1, Yellow 2, Green 3, Blue 4, White 5
-
-            //Create cube and put into rubix cube array
-            cubes.push( Cube::new(gl, shader_program, cube_pos, sideColour));
+            let home = [(i%3) -1, ((i/3)%3) -1, (i/9) -1];  
+
+            //Create cube and put into rubix cube array (sides are coloured from the atlas layout)
+            cubes.push( Cube::new(home, layout));
         }
 
         Self {
             cubePosition: position,
             cubeRotation: [0.0, 0.0, 0.0], 
-            cubes: cubes,
-            rotatingFace: None,
+            cubes,
+            state: CubeState::solved(),
+            supercube: layout == AtlasLayout::Pictures, // Pictures only line up when the centers are upright
+            style: CubieStyle::Bevelled,
+            animation: None,
+            queue: VecDeque::new(),
+            movesQueued: 0,
+            movesFinished: 0,
+            turnSpeed: FACE_ROTATION_SPEED,
             rotationDirection: RotationDirection::Clockwise, 
+            events: Vec::new(),
+            history: History::default(),
+            solves: Vec::new(),
+            replay: None,
+            blindfold: Blindfold::Off,
+            fmc: None,
         }
     }
 
-    //Function to rotate cubes on faces of rubik's cube
-    fn rotate(&mut self, angle: f32) 
+    //Function to advance the animation of the current move, starting the next queued move once it has finished
+    fn animate(&mut self, angle: f32) 
     {
-        //Function is only called if RotationFace is not none
-        let face = self.rotatingFace.as_mut().unwrap(); 
+        if self.animation.is_none() {
+            let Some(next) = self.queue.pop_front() else { return; };
+
+            self.animation = Some(Animation { turn: next, angle: 0.0 });
+            self.events.push(CubeEvent::MoveStarted(next));
+
+            //Inspection is over once the first move is made
+            if let Blindfold::Inspecting { .. } = self.blindfold { self.blindfold = Blindfold::On; }
+        }
+
+        let Some(animation) = self.animation.as_mut() else { return; };
 
-        //If animation hasn't gone 90 degrees, rotate by angle
-        if face.lt_half_pi() {
-            face.rotate(angle);
+        //If animation hasn't reached its final angle, rotate by angle
+        if animation.angle < animation.target() {
+            animation.angle += angle;
 
-        }else{ //Else animation has gone 90 degrees, then stop rotating a side and instead flip the relevant cubies
+        }else{ //Else animation has finished, then stop rotating and instead update the logical state
 
-            let qb_ids = face.get_cubie_ids();
+            let finished = animation.turn;
+            let (face, layers) = finished.turn();
+            self.state.apply_turn(face, layers, finished.quarterTurns);
 
-            //Same for cw && ccw, since to rotate face 90 degrees, can first swap top & btm row and then across diagonal
-            for i in 0..3 {        
-                let (qb1, qb2) = self.cubes.split_at_mut(qb_ids[i + 6]); //9 cubes per face
-                qb1[ qb_ids[i] ].swapCubesRotationMatrices( &mut qb2[0] );                // +6 offset from top to btm row
+            //Move cubies to wherever the logical state now has them
+            self.sync_cubies();
+            self.movesFinished += 1;
+            self.events.push(CubeEvent::MoveFinished(finished));
+            self.events.push(CubeEvent::Progress(cross_progress(&self.state)));
+
+            let solved = self.state.is_solved(self.supercube);
+            if let Some(record) = self.history.record(finished, crate::get_current_time(), solved) {
+                self.solves.push(record);
             }
 
-            //Closure to rotate relevant cubies to complete rotation
-            let mut rotate = |direction| { 
-
-                //Pairs of indices to be swapped accross diagonal of face  //Clockwise  //Counter-Clockwise
-                let ranges = if direction { [(1,3),(2,6),(5,7)] } else { [(0,8),(1,5),(3,7)] };
-
-                //Swap 3 pairs of cubies across the diagonal of the rotating face
-                for (i,j) in ranges {
-                    let (qb1, qb2) = self.cubes.split_at_mut(qb_ids[j]);
-                    qb1[ qb_ids[i] ].swapCubesRotationMatrices( &mut qb2[0] );
-                };
-            };
-            
-            //Get rotation direction from cube
-            let dir = match self.rotationDirection {
-                RotationDirection::Clockwise => true,
-                RotationDirection::CounterClockwise => false,
-            };
-
-            //Find axis to rotate cubes around //Note: negation of alternating faces required to properly rotate
-            let axis = match face {          
-                RotationFace::Front(_, axis)  => { rotate( dir); axis }, //R
-                RotationFace::Back(_, axis)   => { rotate(!dir); axis }, //O
-                RotationFace::Left(_, axis)   => { rotate(!dir); axis }, //G
-                RotationFace::Right(_, axis)  => { rotate( dir); axis }, //B
-                RotationFace::Up(_, axis)     => { rotate( dir); axis }, //Y
-                RotationFace::Down(_, axis)   => { rotate(!dir); axis }, //W
-            };
-
-            //Rotate cubies for the relevant face of the cube
-            for i in qb_ids {
-                self.cubes[i].setCubeRotationMatrix(axis)
+            if solved {
+                self.events.push(CubeEvent::Solved);
+                self.blindfold = Blindfold::Off;
             }
 
             //Set to None after rotation is finished
-            self.rotatingFace = None;          
+            self.animation = None;          
+        }
+
+    }
+
+    // Function to move a replay on by the time since the last frame, drawing the state and move it is up to
+    fn animate_replay(&mut self, delta: f64)
+    {
+        let Some(replay) = self.replay.as_mut() else { return; };
+        replay.advance(delta);
+
+        let finished = replay.finished();
+        let changed = replay.drawn != Some(finished);
+
+        if changed {
+            self.state = replay.state_after(finished);
+            replay.drawn = Some(finished);
+        }
+
+        self.animation = replay.animating().map(|(turn, progress)| {
+            let mut animation = Animation { turn, angle: 0.0 };
+            animation.angle = animation.target() * progress;
+            animation
+        });
+
+        if changed { self.sync_cubies(); }
+    }
+
+    // Method to move and rotate every cubie to match the logical state of the cube
+    fn sync_cubies(&mut self)
+    {
+        for i in 0..27 {
+
+            let position = [(i%3) -1, ((i/3)%3) -1, (i/9) -1];
+            let (home, rotation) = self.state.cubie_at(position);
+
+            //Cubies are stored by home position so find the one which belongs here
+            let index = (home[0] + 1) + 3 * (home[1] + 1) + 9 * (home[2] + 1);
+            self.cubes[index as usize].setCubeTransform(position, &rotation);
+        }
+    }
+
+    // Method to instantly apply a scramble to the cube
+    pub fn applyScramble(&mut self, scramble: &[Move])
+    {
+        self.stopReplay();
+
+        self.state = self.state.apply_moves(scramble);
+
+        self.sync_cubies();
+        let solved = self.isSolved();
+        self.history.restart(&self.state, scramble, solved);
+        self.fmc = None;
+        self.events.push(CubeEvent::Scrambled(scramble.to_vec()));
+    }
+
+    // Method to add moves to be animated one after another. Returns the count of moves queued once they have been
+    pub fn queueMoves(&mut self, moves: &[Move]) -> u64
+    {
+        self.stopReplay();
+
+        //Moves which don't turn anything (eg. R4) are left out
+        for m in moves.iter().filter(|m| m.quarterTurns != 0) {
+            self.queue.push_back(*m);
+            self.movesQueued += 1;
         }
 
+        self.movesQueued
+    }
+
+    // Method to get the count of moves queued so far, and of those how many have finished
+    pub fn moveCounts(&self) -> (u64, u64)
+    {
+        (self.movesQueued, self.movesFinished)
+    }
+
+    // Method to replace the state of the cube, dropping any moves still to be animated (which count as finished)
+    pub fn setState(&mut self, state: CubeState)
+    {
+        self.stopReplay();
+        self.drop_queue();
+
+        self.state = state;
+        self.sync_cubies();
+        let solved = self.isSolved();
+        self.history.restart(&self.state, &[], solved);
+        self.fmc = None;
+    }
+
+    // Method to drop any moves still to be animated (which count as finished)
+    fn drop_queue(&mut self)
+    {
+        self.movesFinished = self.movesQueued;
+        self.queue.clear();
+        self.animation = None;
+    }
+
+    /*
+        Method to start replaying recorded moves from the state they were made from, dropping any moves still to be animated.
+        The cube goes back to how it was once the replay is stopped, or as soon as it is moved, scrambled or set
+    */
+    pub fn startReplay(&mut self, history: &History)
+    {
+        self.stopReplay();
+        self.drop_queue();
+
+        let quarterTurnTime = consts::FRAC_PI_2 as f64 / self.turnSpeed as f64;
+        self.replay = Some(Replay::new(history, self.state.clone(), quarterTurnTime));
+    }
+
+    pub fn stopReplay(&mut self)
+    {
+        let Some(replay) = self.replay.take() else { return; };
+
+        self.state = replay.live;
+        self.animation = None;
+        self.sync_cubies();
+    }
+
+    pub fn replay(&self) -> Option<&Replay>
+    {
+        self.replay.as_ref()
+    }
+
+    pub fn replay_mut(&mut self) -> Option<&mut Replay>
+    {
+        self.replay.as_mut()
+    }
+
+    // Method to start a blindfolded solve: stickers are hidden after inspection (once the first move is made, or the time limit is up)
+    pub fn startBlindfold(&mut self, inspectionTime: Option<f64>)
+    {
+        self.blindfold = Blindfold::Inspecting { until: inspectionTime.map(|time| crate::get_current_time() + time) };
+    }
+
+    // Method to set the blindfold (eg. Blindfold::On to end inspection, or Blindfold::Off to show the stickers again)
+    pub fn setBlindfold(&mut self, blindfold: Blindfold)
+    {
+        self.blindfold = blindfold;
+    }
+
+    // Method to start the hour long time limit of an FMC attempt (from the scramble on the cube)
+    pub fn startFmc(&mut self)
+    {
+        self.fmc = Some(FmcTimer::new(crate::get_current_time()));
+    }
+
+    pub fn stopFmc(&mut self)
+    {
+        self.fmc = None;
+    }
+
+    pub fn fmc(&self) -> Option<&FmcTimer>
+    {
+        self.fmc.as_ref()
+    }
+
+    /*
+        Method to switch between making moves on the scramble and on the inverse scramble (NISS), dropping any moves still to be animated.
+        The cube is set to the other side, with the moves made there so far. Returns true if moves are now made on the inverse
+    */
+    pub fn switchNiss(&mut self) -> bool
+    {
+        self.stopReplay();
+        self.drop_queue();
+
+        self.history.inverse = !self.history.inverse;
+        self.state = self.history.side_state(self.history.inverse);
+        self.sync_cubies();
+
+        self.history.inverse
+    }
+
+    pub fn state(&self) -> &CubeState
+    {
+        &self.state
+    }
+
+    pub fn isSolved(&self) -> bool
+    {
+        self.state.is_solved(self.supercube)
+    }
+
+    pub fn history(&self) -> &History
+    {
+        &self.history
+    }
+
+    // Records of every time the cube was solved from a scramble
+    pub fn solves(&self) -> &[SolveRecord]
+    {
+        &self.solves
+    }
+
+    // Method to set how fast layers turn (in quarter turns per second)
+    pub fn setTurnSpeed(&mut self, quarterTurnsPerSecond: f32)
+    {
+        self.turnSpeed = quarterTurnsPerSecond * consts::FRAC_PI_2;
+    }
+
+    // Method to add an event which happened outside the cube (eg. in the trainer) to be sent along with its own
+    pub fn pushEvent(&mut self, event: CubeEvent)
+    {
+        self.events.push(event);
     }
 
+    // Method to take the events which have happened since this was last called
+    pub fn take_events(&mut self) -> Vec<CubeEvent>
+    {
+        std::mem::take(&mut self.events)
+    }
 
     // Method for Rubik's cube to render itself
     pub fn draw(&mut self, 
         gl_refcell: &RefCell<WebGl2RenderingContext>,
-        shader_program: &WebGlProgram,
-        model_view_matrix_location: &WebGlUniformLocation,
+        renderer: &CubieRenderer,
         delta: f64,
     )
     {
-        //If a side of the cube is currently rotating then will match
-        if self.rotatingFace.is_some() { 
-            self.rotate(FACE_ROTATION_SPEED * delta as f32);
+        //End inspection once its time is up
+        if let Blindfold::Inspecting { until: Some(until) } = self.blindfold {
+            if crate::get_current_time() >= until { self.blindfold = Blindfold::On; }
+        }
+
+        //Let JS know once the time for an FMC attempt is up
+        if let Some(timer) = self.fmc.as_mut() {
+            if timer.expire(crate::get_current_time()) { self.events.push(CubeEvent::FmcTimeUp); }
+        }
+
+        //Turn the layers of the move being animated (if there is one), or of the replay if one is playing
+        match self.replay {
+            Some(_) => self.animate_replay(delta),
+            None    => self.animate(self.turnSpeed * delta as f32),
         }
      
-        //Loop through every cube in rubix and call draw on it
-        for (i, cube) in self.cubes.iter_mut().enumerate() {
+        let mut instanceData = Vec::with_capacity(self.cubes.len() * INSTANCE_FLOATS);
+
+        //Loop through every cube in rubix and gather its instance data
+        for cube in self.cubes.iter() {
 
             //Create model matrix to position element in world space
             let mut model_view_matrix = Mat4::identity();
@@ -136,27 +380,25 @@ impl Rubix {
             model_view_matrix.translate(&self.cubePosition);  //NOTE: (This operation will be applied last);
 
             //Rotate entire cube
-            model_view_matrix.rotate( self.cubeRotation[0], &vec![0.0, 1.0, 0.0]);
-            model_view_matrix.rotate( self.cubeRotation[1], &vec![1.0, 0.0, 0.0]);
-
-            //Animation for rotating a face of the cube if one has been set in motion
-            if let Some(face) = self.rotatingFace.as_ref() { 
-
-                match face {
-                    RotationFace::Front(angle, axis)  if (0..9).contains(&i)    =>  { model_view_matrix.rotate(*angle, axis); }, 
-                    RotationFace::Back(angle, axis)   if (18..27).contains(&i)  =>  { model_view_matrix.rotate(*angle, axis); }, 
-                    RotationFace::Left(angle, axis)   if i % 3 == 2                   =>  { model_view_matrix.rotate(*angle, axis); }, 
-                    RotationFace::Right(angle, axis)  if i % 3 == 0                   =>  { model_view_matrix.rotate(*angle, axis); }, 
-                    RotationFace::Up(angle, axis)     if (6..9).contains(&(i%9)) => { model_view_matrix.rotate(*angle, axis); }, 
-                    RotationFace::Down(angle, axis)   if (0..3).contains(&(i%9)) => { model_view_matrix.rotate(*angle, axis); }, 
-                    _ => (), //Needed for exhaustive pattern match
+            model_view_matrix.rotate( self.cubeRotation[0], &[0.0, 1.0, 0.0]);
+            model_view_matrix.rotate( self.cubeRotation[1], &[1.0, 0.0, 0.0]);
+
+            //Animation for turning layers of the cube if a move has been set in motion
+            if let Some(animation) = self.animation.as_ref() { 
+
+                if animation.contains(cube.position()) {
+                    model_view_matrix.rotate(animation.angle.min(animation.target()), &animation.axis());
                 }
             }
 
-            //Make draw call on individual cubie
-            cube.draw(gl_refcell, shader_program, &mut model_view_matrix, model_view_matrix_location);
+            cube.push_instance_data(&mut model_view_matrix, &mut instanceData);
         }
 
+        //Draw every cubie with a single draw call
+        //Stickers are hidden while blindfolded, unless a replay is being watched
+        let hidden = self.blindfold == Blindfold::On && self.replay.is_none();
+        renderer.draw(&gl_refcell.borrow(), &instanceData, self.style, hidden);
+
     }
 
     // Method to modify angle of rotation for entire cube 
@@ -168,36 +410,46 @@ impl Rubix {
             "ArrowRight" =>  self.cubeRotation[0] += CUBE_ROTATION_SPEED,
             "ArrowUp"    =>  self.cubeRotation[1] -= CUBE_ROTATION_SPEED,
             "ArrowDown"  =>  self.cubeRotation[1] += CUBE_ROTATION_SPEED,
-            _ => (), //Needed to satisfy non-exhaustive pattern complaint
+            _ => return, //Needed to satisfy non-exhaustive pattern complaint
         }
+
+        self.events.push(CubeEvent::ViewChanged { yaw: self.cubeRotation[0], pitch: self.cubeRotation[1] });
     }
 
     // Method to set in motion the rotation of a given cube face
     pub fn rotateFace(&mut self, keyCode: String){
 
-        if self.rotatingFace.is_some() { return; } //Early return if rotation already in progress
+        //Early return if rotation already in progress (or a replay is being shown)
+        if self.animation.is_some() || !self.queue.is_empty() || self.replay.is_some() { return; }
 
-        //Axis of rotation depends on rotation direction // +1.0 for clockwise and -1.0 for counter-clockwise
-        let dir = self.rotationDirection as i32 as f32; // Kinda ugly
-        let mut axis = [0.0, 0.0, 0.0];
-        
-        self.rotatingFace = match keyCode.as_str() {
-
-            "KeyR"  => { axis[2] =  dir; Some( RotationFace::Front( 0.0, axis) ) },
-            "KeyO"  => { axis[2] = -dir; Some( RotationFace::Back(  0.0, axis) ) }, 
-            "KeyG"  => { axis[0] =  dir; Some( RotationFace::Right( 0.0, axis) ) },  
-            "KeyB"  => { axis[0] = -dir; Some( RotationFace::Left(  0.0, axis) ) }, 
-            "KeyY"  => { axis[1] = -dir; Some( RotationFace::Up(    0.0, axis) ) },   
-            "KeyW"  => { axis[1] =  dir; Some( RotationFace::Down(  0.0, axis) ) }, 
-            _ => None, //Needed to satisfy non-exhaustive pattern complaint
-        }
+        //Note: Keys are named after the colour of each center, so eg. red is the back of the logical cube (see state.rs)
+        let face = match keyCode.as_str() {
+
+            "KeyR"  => Face::B, // z = -1
+            "KeyO"  => Face::F, // z = +1
+            "KeyG"  => Face::L, // x = -1
+            "KeyB"  => Face::R, // x = +1
+            "KeyY"  => Face::U, // y = +1
+            "KeyW"  => Face::D, // y = -1
+            _ => return, //Needed to satisfy non-exhaustive pattern complaint
+        };
+
+        self.queueMoves(&[Move::new(Layers::Face(face), self.rotationDirection as i32)]);
+    }
+
+    // Method to switch between drawing lit bevelled cubies and flat unlit ones
+    pub fn toggleStyle(&mut self) -> CubieStyle
+    {
+        self.style = match self.style {
+            CubieStyle::Flat     => CubieStyle::Bevelled,
+            CubieStyle::Bevelled => CubieStyle::Flat,
+        };
+        self.style
     }
 
     // Method to change direction of rotation for the faces of the cube
     pub fn changeRotationDirection(&mut self) -> RotationDirection
     {
-        if self.rotatingFace.is_some() { return self.rotationDirection; } //Early return if rotation already in progress
-
         match self.rotationDirection {
 
             RotationDirection::Clockwise        => { self.rotationDirection = RotationDirection::CounterClockwise; 
@@ -207,61 +459,48 @@ impl Rubix {
         }
     }
 
+    pub fn setRotationDirection(&mut self, direction: RotationDirection)
+    {
+        self.rotationDirection = direction;
+    }
+
 }
 
 
 /*
-    Enum to keep track of which face is currently rotating and how much it has rotated
+    Struct to keep track of the move currently being animated and how much it has rotated
 */
 #[derive(Debug)] 
-enum RotationFace {  
-    Front(f32, [f32; 3]),   // Angle - starts at zero and goes to pi/2 ie. 90 degrees
-    Back(f32, [f32; 3]),    // Axis of Rotation - changes sign for cw or ccw
-    Left(f32, [f32; 3]),
-    Right(f32, [f32; 3]),
-    Up(f32, [f32; 3]),
-    Down(f32, [f32; 3]),
+struct Animation {  
+    turn: Move,
+    angle: f32, // Starts at zero and goes up to the target angle of the move
 }
 
-impl RotationFace {
+impl Animation {
 
-    // Method to get the ids of the cubes within a faces relative to its position in the rubik cube array
-    fn get_cubie_ids(&self) -> [usize; 9] 
+    // Method to get the angle the layers turn through ie. 90 degrees, or 180 degrees for half turns
+    fn target(&self) -> f32
     {
-        match self {  
-            RotationFace::Front(_, _)  => array::from_fn(|i| i),       // 0..=8
-            RotationFace::Back(_, _)   => array::from_fn(|i| i + 18),  // 18..=26
-            RotationFace::Left(_, _)   => array::from_fn(|i| i * 3 + 2),     // 3,6,9,..,24
-            RotationFace::Right(_, _)  => array::from_fn(|i| i * 3), //5,8,11,..,26
-            RotationFace::Up(_, _)     => array::from_fn(|i| (i/3)*9 + i%3 +6), //6,7,8, 15,16,17, 24,25,26
-            RotationFace::Down(_, _)   => array::from_fn(|i| (i/3)*9 + i%3),    //0,1,2,  9,10,11, 18,19,20
+        match self.turn.quarterTurns {
+            0 => 0.0,
+            2 => consts::PI,
+            _ => consts::FRAC_PI_2,
         }
     }
 
-    // Method to check if rotation is less than half pi
-    fn lt_half_pi(&self) -> bool
+    // Method to get the axis of rotation, which changes sign for cw or ccw
+    fn axis(&self) -> [f32; 3]
     {
-        match self {  
-            RotationFace::Front(angle, _)  => *angle < (consts::PI / 2.0),
-            RotationFace::Back(angle, _)   => *angle < (consts::PI / 2.0), 
-            RotationFace::Left(angle, _)   => *angle < (consts::PI / 2.0),
-            RotationFace::Right(angle, _)  => *angle < (consts::PI / 2.0),
-            RotationFace::Up(angle, _)     => *angle < (consts::PI / 2.0),
-            RotationFace::Down(angle, _)   => *angle < (consts::PI / 2.0),
-        }
+        //Clockwise when seen from outside the face is a positive rotation around the inward normal
+        let (face, _) = self.turn.turn();
+        let sign = if self.turn.quarterTurns == 3 { 1.0 } else { -1.0 };
+        face.normal().map(|x| x as f32 * sign)
     }
 
-    // Method to rotate a rotation by a given arc length
-    fn rotate(&mut self, arclen: f32)
+    // Method to check if the cubie at a position is in one of the layers being turned
+    fn contains(&self, position: IVec3) -> bool
     {
-        match self {  
-            RotationFace::Front(angle, _)  => *angle += arclen,
-            RotationFace::Back(angle, _)   => *angle += arclen, 
-            RotationFace::Left(angle, _)   => *angle += arclen, 
-            RotationFace::Right(angle, _)  => *angle += arclen, 
-            RotationFace::Up(angle, _)     => *angle += arclen, 
-            RotationFace::Down(angle, _)   => *angle += arclen, 
-        }
+        let (face, layers) = self.turn.turn();
+        layers[(1 - dot(position, face.normal())) as usize]
     }
-
 }
@@ -0,0 +1,50 @@
+
+use crate::notation::{Layers, Move};
+use crate::state::{dot, Face};
+
+//Number of moves in a scramble when no length is given
+pub const SCRAMBLE_LENGTH: usize = 25;
+
+/*
+    Function to generate a random move scramble of face turns, given a source of random numbers between 0.0 and 1.0.
+    Never turns the same face twice in a row, nor a face again after only its opposite face (eg. R L R) was turned
+*/
+pub fn random_scramble(length: usize, mut random: impl FnMut() -> f64) -> Vec<Move>
+{
+    let mut scramble: Vec<Move> = Vec::with_capacity(length);
+    let mut faces: Vec<Face> = Vec::with_capacity(length);
+
+    while scramble.len() < length {
+
+        let face = Face::ALL[(random() * 6.0) as usize % 6];
+
+        let repeated = match faces.as_slice() {
+            [.., last] if *last == face => true,
+            [.., before, last] => *before == face && dot(last.normal(), face.normal()) == -1,
+            _ => false,
+        };
+        if repeated { continue; }
+
+        //Clockwise, half or counter-clockwise turn
+        let quarterTurns = 1 + (random() * 3.0) as i32 % 3;
+
+        faces.push(face);
+        scramble.push(Move::new(Layers::Face(face), quarterTurns));
+    }
+
+    scramble
+}
+
+// Source of random numbers between 0.0 and 1.0 for tests, which gives the same numbers every run for a seed
+#[cfg(test)]
+pub fn seeded_random(seed: u64) -> impl FnMut() -> f64
+{
+    let mut state = seed.max(1);
+
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -1,16 +1,16 @@
 
-use wasm_bindgen::prelude::*;
 use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader};
+use crate::error::RubixError;
 
 /*
     Helper function (called by setup_shaders (below)) to compile shaders for current webGL context
 */
-fn create_shader(gl: &WebGl2RenderingContext, shader_type: u32, source: &str) -> Result<WebGlShader, JsValue> {
+fn create_shader(gl: &WebGl2RenderingContext, shader_type: u32, source: &str) -> Result<WebGlShader, RubixError> {
     
     //Create a shader of the given type for the currently webGL context
     let shader = gl
         .create_shader(shader_type)
-        .ok_or_else(|| JsValue::from_str("Unable to create shader object"))?;
+        .ok_or(RubixError::GlObject("shader"))?;
 
     //Transfer the shader source code to the currently bound shader
     gl.shader_source(&shader, source);
@@ -18,7 +18,7 @@ fn create_shader(gl: &WebGl2RenderingContext, shader_type: u32, source: &str) ->
     //Compile the currently bound shader
     gl.compile_shader(&shader);
 
-    //If shader compiled successfuly then return the shader else JSValue error
+    //If shader compiled successfuly then return the shader else error with the compiler's info log
     if gl
         .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
         .as_bool()
@@ -26,25 +26,26 @@ fn create_shader(gl: &WebGl2RenderingContext, shader_type: u32, source: &str) ->
     {
         Ok(shader)
     } else {
-        crate::log(&format!("ERROR: Unable to Compile Shader")); 
+        let stage = if shader_type == WebGl2RenderingContext::VERTEX_SHADER { "vertex" } else { "fragment" };
+        let log = gl.get_shader_info_log(&shader).unwrap_or_else(|| "Unknown error creating shader".into());
 
-        Err(JsValue::from_str(&gl.get_shader_info_log(&shader).unwrap_or_else(|| "Unknown error creating shader".into()) ))
+        Err(RubixError::ShaderCompile { stage, log })
     }
 }
 
 /*
     Function to create adn link shaders. Returns a working shader program or else err
 */
-pub fn setup_shaders(gl: &WebGl2RenderingContext, vertex_shader_str: &str, fragment_shader_str: &str) -> Result<WebGlProgram, JsValue> {
+pub fn setup_shaders(gl: &WebGl2RenderingContext, vertex_shader_str: &str, fragment_shader_str: &str) -> Result<WebGlProgram, RubixError> {
     
     //Compile vertex shader via helper function above
-    let vertex_shader = create_shader(&gl, WebGl2RenderingContext::VERTEX_SHADER, vertex_shader_str).unwrap();
+    let vertex_shader = create_shader(gl, WebGl2RenderingContext::VERTEX_SHADER, vertex_shader_str)?;
     
     //Compile vertex shader via helper function above
-    let fragment_shader = create_shader(&gl,WebGl2RenderingContext::FRAGMENT_SHADER, fragment_shader_str).unwrap();
+    let fragment_shader = create_shader(gl,WebGl2RenderingContext::FRAGMENT_SHADER, fragment_shader_str)?;
 
     //Create webGL shader program
-    let shader_program = gl.create_program().unwrap();
+    let shader_program = gl.create_program().ok_or(RubixError::GlObject("shader program"))?;
 
     //Attach vertex and fragment shader to webGL shader program
     gl.attach_shader(&shader_program, &vertex_shader);
@@ -53,8 +54,12 @@ pub fn setup_shaders(gl: &WebGl2RenderingContext, vertex_shader_str: &str, fragm
     //Link the shader program to the currently webGL context
     gl.link_program(&shader_program);
 
+    //Shaders are only needed until linked, so flag them to be freed along with the program
+    gl.delete_shader(Some(&vertex_shader));
+    gl.delete_shader(Some(&fragment_shader));
+
     //If shader program successfully linked to currently bounded webGl context then have webGL context use that shader program
-    //else return JSValue error
+    //else return error with the linker's info log
     if gl
         .get_program_parameter(&shader_program, WebGl2RenderingContext::LINK_STATUS)
         .as_bool()
@@ -65,10 +70,8 @@ pub fn setup_shaders(gl: &WebGl2RenderingContext, vertex_shader_str: &str, fragm
         Ok(shader_program)
         
     } else {
-        crate::log(&format!("ERROR: Unable to Link Shader")); 
-
-        return Err(JsValue::from_str(&gl.get_program_info_log(&shader_program)
-                                        .unwrap_or_else(|| "Unknown error linking program".into()) ));
+        Err(RubixError::ShaderLink(gl.get_program_info_log(&shader_program)
+                                        .unwrap_or_else(|| "Unknown error linking program".into()) ))
     }
 }
 
@@ -0,0 +1,208 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::notation::{format_moves, parse_moves, Axis, Layers, Move, Slice};
+use crate::state::Face;
+
+/*
+    Simplifying algorithms, by cancelling and merging moves which turn layers around the same axis.
+    Every layer turned around one axis commutes with the others (eg. R L R = R2 L), so each run of moves around the
+    same axis is boiled down to how far it turns each of the 3 layers, then written out again in as few moves as possible.
+    This also swaps between equivalent wide, slice and rotation moves (eg. R M' = Rw, R L' x' = M)
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const SIMPLIFIED_ALGORITHM: &'static str = r#"
+/** Algorithm returned by simplifyAlgorithm, along with its move count before and after simplifying */
+export interface SimplifiedAlgorithm {
+    algorithm: string;
+    before: number;
+    after: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SimplifiedAlgorithm")]
+    pub type SimplifiedAlgorithm;
+}
+
+/*
+ * Simplify an algorithm in standard notation (eg. "R R' U U U" becomes "U'"). Throws a RubixError if the notation is invalid
+ */
+#[wasm_bindgen(js_name = simplifyAlgorithm)]
+pub fn simplifyAlgorithm(algorithm: &str) -> Result<SimplifiedAlgorithm, JsValue>
+{
+    let moves = parse_moves(algorithm)?;
+    let simplified = simplify(&moves);
+
+    let object = js_sys::Object::new();
+    let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+    set("algorithm", JsValue::from_str(&format_moves(&simplified)));
+    set("before", JsValue::from_f64(moves.len() as f64));
+    set("after", JsValue::from_f64(simplified.len() as f64));
+
+    Ok(object.unchecked_into())
+}
+
+/*
+    Function to simplify a sequence of moves. Runs of moves around the same axis are only rewritten when that saves
+    moves, so algorithms which can't be shortened keep the moves (and order) they were written with
+*/
+pub fn simplify(moves: &[Move]) -> Vec<Move>
+{
+    let mut simplified: Vec<Move> = Vec::with_capacity(moves.len());
+
+    for m in moves.iter().filter(|m| m.quarterTurns != 0) {
+
+        //Moves at the end which turn around the same axis as this one (they all commute with each other)
+        let axis = axis_of(*m).0;
+        let start = simplified.iter().rposition(|previous| axis_of(*previous).0 != axis).map_or(0, |i| i + 1);
+
+        let mut run = simplified.split_off(start);
+        run.push(*m);
+
+        let shortest = shortest_moves(axis, layer_turns(&run));
+        if shortest.len() < run.len() {
+            simplified.extend(shortest);
+        } else {
+            simplified.extend(run);
+        }
+    }
+
+    simplified
+}
+
+// Positive face of each axis (ie. the face x, y and z rotations follow)
+const AXIS_FACES: [Face; 3] = [Face::R, Face::U, Face::F];
+
+// Get the axis (0 for x, 1 for y, 2 for z) a move turns around, and if it turns clockwise around the positive face of the axis
+fn axis_of(m: Move) -> (usize, bool)
+{
+    let (face, _) = m.turn();
+    let normal = face.normal();
+    let axis = normal.iter().position(|x| *x != 0).unwrap_or(0);
+
+    (axis, normal[axis] > 0)
+}
+
+// Get how many quarter turns (clockwise around the positive face of their axis) a run of moves turns each layer by.
+// Layers are ordered from the negative face (eg. L) to the positive face (eg. R)
+fn layer_turns(run: &[Move]) -> [i32; 3]
+{
+    let mut turns = [0; 3];
+
+    for m in run {
+        let (_, layers) = m.turn();
+        let (_, positive) = axis_of(*m);
+
+        for (i, turned) in layers.into_iter().enumerate() {
+            if !turned { continue; }
+
+            //Layers of a move are counted from its face
+            let (layer, quarterTurns) = if positive { (2 - i, m.quarterTurns) } else { (i, -m.quarterTurns) };
+            turns[layer] = (turns[layer] + quarterTurns).rem_euclid(4);
+        }
+    }
+
+    turns
+}
+
+// Get a move turning layers by quarter turns around the positive face of an axis (no move if it doesn't turn)
+fn axis_move(layers: Layers, quarterTurns: i32) -> Option<Move>
+{
+    if quarterTurns.rem_euclid(4) == 0 { return None; }
+
+    let (_, positive) = axis_of(Move::new(layers, 1));
+    Some(Move::new(layers, if positive { quarterTurns } else { -quarterTurns }))
+}
+
+// Get the fewest moves which turn the layers around an axis as given, preferring face turns over wide, slice and rotation moves
+fn shortest_moves(axis: usize, turns: [i32; 3]) -> Vec<Move>
+{
+    let positive = AXIS_FACES[axis];
+    let negative = Face::from_normal(positive.normal().map(|x| -x)).unwrap_or(positive);
+    let slice = Layers::Slice([Slice::M, Slice::E, Slice::S][axis]);
+    let rotation = Layers::Rotation([Axis::X, Axis::Y, Axis::Z][axis]);
+
+    let mut best: Option<((usize, usize), Vec<Move>)> = None;
+
+    //Whole cube rotation taking care of some of the turns, then the middle layer either turned by itself or with a face
+    for rotated in 0..4 {
+
+        let [n, middle, p] = turns.map(|x| x - rotated);
+
+        let candidates = [
+            [axis_move(Layers::Face(positive), p), axis_move(Layers::Face(negative), n), axis_move(slice, middle)],
+            [axis_move(Layers::Wide(positive), middle), axis_move(Layers::Face(positive), p - middle), axis_move(Layers::Face(negative), n)],
+            [axis_move(Layers::Face(positive), p), axis_move(Layers::Wide(negative), middle), axis_move(Layers::Face(negative), n - middle)],
+        ];
+
+        for candidate in candidates {
+
+            let mut moves: Vec<Move> = candidate.into_iter().flatten().collect();
+            moves.extend(axis_move(rotation, rotated));
+
+            //Fewest moves, then fewest moves which aren't face turns
+            let cost = (moves.len(), moves.iter().filter(|m| !matches!(m.layers, Layers::Face(_))).count());
+
+            if best.as_ref().is_none_or(|(bestCost, _)| cost < *bestCost) {
+                best = Some((cost, moves));
+            }
+        }
+    }
+
+    best.map(|(_, moves)| moves).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::seeded_random;
+    use crate::state::CubeState;
+
+    fn simplified(algorithm: &str) -> String
+    {
+        format_moves(&simplify(&parse_moves(algorithm).unwrap()))
+    }
+
+    #[test]
+    fn documented_cases()
+    {
+        assert_eq!(simplified("R R'"), "");
+        assert_eq!(simplified("U U U"), "U'");
+        assert_eq!(simplified("R L R"), "R2 L");
+        assert_eq!(simplified("R L' x'"), "M");
+        assert_eq!(simplified("R M'"), "Rw");
+        assert_eq!(simplified("R L R U U'"), "R2 L");
+    }
+
+    #[test]
+    fn unchanged_when_nothing_cancels()
+    {
+        assert_eq!(simplified("R U R' U'"), "R U R' U'");
+        assert_eq!(simplified("M2 U M2 U2 M2 U M2"), "M2 U M2 U2 M2 U M2");
+    }
+
+    #[test]
+    fn same_state_after_simplifying()
+    {
+        let mut random = seeded_random(37);
+        let layers: Vec<Layers> = Face::ALL.into_iter().flat_map(|face| [Layers::Face(face), Layers::Wide(face)])
+            .chain([Slice::M, Slice::E, Slice::S].map(Layers::Slice))
+            .chain([Axis::X, Axis::Y, Axis::Z].map(Layers::Rotation))
+            .collect();
+
+        for _ in 0..500 {
+            let moves: Vec<Move> = (0..12).map(|_| {
+                let layers = layers[(random() * layers.len() as f64) as usize];
+                Move::new(layers, (random() * 4.0) as i32)
+            }).collect();
+
+            let simplified = simplify(&moves);
+            assert!(simplified.len() <= moves.len());
+            assert_eq!(CubeState::solved().apply_moves(&simplified), CubeState::solved().apply_moves(&moves), "{}", format_moves(&moves));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::history::TimedMove;
+use crate::metrics::Metrics;
+use crate::notation::{format_moves, Move};
+use crate::progress::{last_layer_oriented, progress_for};
+use crate::state::{CubeState, Face};
+
+/*
+    Splits of a solve into the steps of CFOP: the cross, the four F2L pairs, OLL and PLL.
+    The moves are replayed from the state the solve started from, and each step ends with the first move after the last step
+    where it is done. The cross colour is the one whose F2L is finished first, so solves on any colour (held any way) are split
+*/
+
+// Names of the steps, in the order they are done
+const STEPS: [&str; 7] = ["cross", "f2l1", "f2l2", "f2l3", "f2l4", "oll", "pll"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Split {
+    pub step: &'static str,
+    pub moves: Vec<Move>,
+    pub time: f64, // Seconds from the end of the last step (or the first move) to the end of this one
+}
+
+impl Split {
+
+    // Method to build the plain JS object given to JS (see SolveSplit)
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("step", JsValue::from_str(self.step));
+        set("moves", JsValue::from_str(&format_moves(&self.moves)));
+        set("moveCount", JsValue::from(Metrics::of(&self.moves).htm));
+        set("time", JsValue::from_f64(self.time));
+
+        object.into()
+    }
+}
+
+// Split a solve (which must end solved) into its CFOP steps
+pub fn cfop_splits(start: &CubeState, moves: &[TimedMove]) -> Vec<Split>
+{
+    if moves.is_empty() { return Vec::new(); }
+
+    //State before any moves, then after each one
+    let mut states = vec![start.clone()];
+    for m in moves {
+        states.push(states[states.len() - 1].apply_moves(&[m.turn]));
+    }
+
+    let Some(ends) = Face::ALL.into_iter().map(|colour| step_ends(&states, colour)).min_by_key(|ends| ends[4]) else { return Vec::new(); };
+
+    //Time of each state is when the move before it finished (the first state is counted from the first move)
+    let time = |state: usize| moves[state.saturating_sub(1)].time;
+
+    let mut previous = 0;
+    STEPS.iter().zip(ends).map(|(step, end)| {
+        let split = Split { step, moves: moves[previous..end].iter().map(|m| m.turn).collect(), time: time(end) - time(previous) };
+        previous = end;
+        split
+    }).collect()
+}
+
+// Find the index of the state each step ends at for a cross colour
+fn step_ends(states: &[CubeState], colour: Face) -> [usize; 7]
+{
+    let done = |state: &CubeState, step: usize| {
+        let progress = progress_for(state, colour);
+        match step {
+            0     => progress.crossEdges == 4,
+            1..=4 => progress.crossEdges == 4 && progress.solvedPairs as usize >= step,
+            5     => progress.crossEdges == 4 && progress.solvedPairs == 4 && last_layer_oriented(state, colour),
+            _     => false,
+        }
+    };
+
+    let last = states.len() - 1;
+    let mut ends = [last; 7];
+    let mut previous = 0;
+
+    //The last step (PLL) always ends with the solve
+    for (step, end) in ends.iter_mut().enumerate().take(6) {
+        *end = (previous..last).find(|i| done(&states[*i], step)).unwrap_or(last);
+        previous = *end;
+    }
+
+    ends
+}
@@ -0,0 +1,484 @@
+
+/*
+    Logical state of the rubik's cube, kept separately from the cubies that are drawn.
+    Each of the 54 stickers is tracked individually along with the orientation of the 6 center pieces,
+    so that both ordinary cubes and picture cubes (supercubes) can be checked for being solved.
+*/
+
+use crate::error::RubixError;
+use crate::notation::Move;
+
+pub type IVec3 = [i32; 3];
+
+/*
+    Faces of the cube in the order used by facelet strings (ie. U R F D L B)
+    Viewed from the front (ie. from the camera) U is +y, R is +x and F is +z
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face { U, R, F, D, L, B }
+
+impl Face {
+
+    pub const ALL: [Face; 6] = [Face::U, Face::R, Face::F, Face::D, Face::L, Face::B];
+
+    pub fn index(self) -> usize
+    {
+        self as usize
+    }
+
+    // Outward facing normal of the face
+    pub fn normal(self) -> IVec3
+    {
+        match self {
+            Face::U => [ 0,  1,  0],
+            Face::R => [ 1,  0,  0],
+            Face::F => [ 0,  0,  1],
+            Face::D => [ 0, -1,  0],
+            Face::L => [-1,  0,  0],
+            Face::B => [ 0,  0, -1],
+        }
+    }
+
+    // Direction pointing right when looking straight at the face from outside the cube
+    pub fn right(self) -> IVec3
+    {
+        match self {
+            Face::U | Face::F | Face::D => [1, 0, 0],
+            Face::R => [ 0, 0, -1],
+            Face::L => [ 0, 0,  1],
+            Face::B => [-1, 0,  0],
+        }
+    }
+
+    // Direction pointing up when looking straight at the face from outside the cube
+    pub fn up(self) -> IVec3
+    {
+        match self {
+            Face::U => [0, 0, -1],
+            Face::D => [0, 0,  1],
+            _ => [0, 1, 0],
+        }
+    }
+
+    // Index into the sticker texture atlas (Red 0, Orange 1, Yellow 2, Green 3, Blue 4, White 5)
+    pub fn colour_index(self) -> u32
+    {
+        match self {
+            Face::U => 2, //Yellow
+            Face::R => 4, //Blue
+            Face::F => 1, //Orange
+            Face::D => 5, //White
+            Face::L => 3, //Green
+            Face::B => 0, //Red
+        }
+    }
+
+    // Face whose outward normal is the given vector
+    pub fn from_normal(normal: IVec3) -> Option<Face>
+    {
+        Face::ALL.into_iter().find(|face| face.normal() == normal)
+    }
+
+    // Letter naming the face in facelet strings and cube notation
+    pub fn letter(self) -> char
+    {
+        ['U', 'R', 'F', 'D', 'L', 'B'][self.index()]
+    }
+
+    pub fn from_letter(letter: char) -> Option<Face>
+    {
+        Face::ALL.into_iter().find(|face| face.letter() == letter)
+    }
+}
+
+/*
+    Small integer vector helpers (all cube geometry is done on the integer lattice so that it is exact)
+*/
+pub fn dot(a: IVec3, b: IVec3) -> i32
+{
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: IVec3, b: IVec3) -> IVec3
+{
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+// Rotate vector by a quarter turn counter-clockwise around the given unit axis (ie. right hand rule)
+pub fn quarter_turn(v: IVec3, axis: IVec3) -> IVec3
+{
+    let c = cross(axis, v);
+    let d = dot(axis, v);
+    [axis[0] * d + c[0], axis[1] * d + c[1], axis[2] * d + c[2]]
+}
+
+fn negate(v: IVec3) -> IVec3
+{
+    v.map(|x| -x)
+}
+
+fn det(a: IVec3, b: IVec3, c: IVec3) -> i32
+{
+    dot(cross(a, b), c)
+}
+
+// Get every position in the cube other than the core, along with the faces each one is on
+fn piece_positions() -> impl Iterator<Item = (IVec3, Vec<Face>)>
+{
+    (0..27)
+        .map(|i| [(i % 3) - 1, ((i / 3) % 3) - 1, (i / 9) - 1])
+        .filter(|position| *position != [0, 0, 0])
+        .map(|position| (position, Face::ALL.into_iter().filter(|face| dot(position, face.normal()) == 1).collect()))
+}
+
+/*
+    Sticker slots are numbered face by face (in U R F D L B order), row by row from the top left of the face
+*/
+pub const NUM_STICKERS: usize = 54;
+
+pub fn slot_index(position: IVec3, face: Face) -> usize
+{
+    let row = 1 - dot(position, face.up());
+    let col = 1 + dot(position, face.right());
+    face.index() * 9 + (row * 3 + col) as usize
+}
+
+// Get the position of the cubie a slot belongs to along with the face the slot is on
+pub fn slot_position(slot: usize) -> (IVec3, Face)
+{
+    let face = Face::ALL[slot / 9];
+    let (row, col) = ((slot % 9 / 3) as i32, (slot % 3) as i32);
+    let (n, r, u) = (face.normal(), face.right(), face.up());
+
+    let position = array_from_fn(|i| n[i] + r[i] * (col - 1) + u[i] * (1 - row));
+    (position, face)
+}
+
+fn array_from_fn(f: impl FnMut(usize) -> i32) -> IVec3
+{
+    std::array::from_fn(f)
+}
+
+/*
+    Rotation of a cubie as an integer 3x3 matrix (row major) which maps its home orientation onto its current one
+*/
+pub type Rotation = [[i32; 3]; 3];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CubeState {
+    stickers: [u8; NUM_STICKERS], // Home slot of the sticker currently in each slot
+    centerTwists: [u8; 6],        // Clockwise quarter turns of each center piece (by home face) away from upright
+}
+
+impl Default for CubeState {
+
+    fn default() -> Self
+    {
+        Self::solved()
+    }
+}
+
+impl CubeState {
+
+    pub fn solved() -> Self
+    {
+        Self {
+            stickers: std::array::from_fn(|i| i as u8),
+            centerTwists: [0; 6],
+        }
+    }
+
+    // Get the face a sticker was originally on (ie. its colour)
+    pub fn sticker_face(&self, slot: usize) -> Face
+    {
+        Face::ALL[self.stickers[slot] as usize / 9]
+    }
+
+    // Get the home slot of the sticker currently in a slot
+    pub fn sticker(&self, slot: usize) -> usize
+    {
+        self.stickers[slot] as usize
+    }
+
+    // Get the clockwise quarter turns of the center piece which started on the given face
+    pub fn center_twist(&self, home: Face) -> u8
+    {
+        self.centerTwists[home.index()]
+    }
+
+    /*
+        Method to turn layers of the cube clockwise (as seen when looking at the given face) by a number of quarter turns.
+        Layers are counted from the given face ie. [true, false, false] is a face turn, [false, true, false] a slice turn
+        and [true, true, true] a rotation of the whole cube
+    */
+    pub fn apply_turn(&mut self, face: Face, layers: [bool; 3], quarterTurns: i32)
+    {
+        //Clockwise when seen from outside the face is counter-clockwise around the inward normal
+        let axis = negate(face.normal());
+
+        for _ in 0..quarterTurns.rem_euclid(4) {
+
+            let mut stickers = self.stickers;
+            let mut twists = self.centerTwists;
+
+            for (slot, sticker) in self.stickers.iter().enumerate() {
+
+                let (position, slotFace) = slot_position(slot);
+                if !layers[(1 - dot(position, face.normal())) as usize] { continue; }
+
+                let newPosition = quarter_turn(position, axis);
+                let newFace = Face::from_normal(quarter_turn(slotFace.normal(), axis)).unwrap();
+                stickers[slot_index(newPosition, newFace)] = *sticker;
+
+                //Center pieces also keep track of how far they have been twisted
+                if position == slotFace.normal() {
+                    let delta = twist_between(newFace, quarter_turn(slotFace.up(), axis));
+                    let home = *sticker as usize / 9;
+                    twists[home] = (twists[home] + delta) % 4;
+                }
+            }
+
+            self.stickers = stickers;
+            self.centerTwists = twists;
+        }
+    }
+
+    // Get the state after a sequence of moves
+    pub fn apply_moves(&self, moves: &[Move]) -> CubeState
+    {
+        let mut state = self.clone();
+
+        for m in moves {
+            let (face, layers) = m.turn();
+            state.apply_turn(face, layers, m.quarterTurns);
+        }
+
+        state
+    }
+
+    // Check if every face is a single colour. For supercubes the centers must also be upright
+    pub fn is_solved(&self, supercube: bool) -> bool
+    {
+        let uniform = (0..NUM_STICKERS).all(|slot| self.sticker_face(slot) == self.sticker_face(slot / 9 * 9 + 4));
+
+        if !uniform || !supercube { return uniform; }
+
+        //A solved supercube is the solved state seen from one of the 24 possible orientations
+        whole_cube_orientations().iter().any(|orientation| orientation == self)
+    }
+
+    // Write out the state as a facelet string ie. the colour (as a face letter) of every sticker slot in order
+    pub fn to_facelets(&self) -> String
+    {
+        (0..NUM_STICKERS).map(|slot| self.sticker_face(slot).letter()).collect()
+    }
+
+    /*
+        Method to create a state from a facelet string: 54 face letters (U R F D L B) naming the colour of each sticker,
+        face by face in U R F D L B order and row by row from the top left of each face (as seen from outside the cube).
+        Checks that the stickers make up real pieces and that the cube could be solved. Centers are taken to be upright
+    */
+    pub fn from_facelets(facelets: &str) -> Result<Self, RubixError>
+    {
+        let invalid = |message: String| RubixError::InvalidState(message);
+
+        let colours = facelets.chars().filter(|c| !c.is_whitespace())
+            .map(|c| Face::from_letter(c.to_ascii_uppercase()).ok_or_else(|| invalid(format!("\"{}\" is not a face letter", c))))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if colours.len() != NUM_STICKERS {
+            return Err(invalid(format!("expected {} facelets but got {}", NUM_STICKERS, colours.len())));
+        }
+
+        for face in Face::ALL {
+            let count = colours.iter().filter(|colour| **colour == face).count();
+            if count != 9 { return Err(invalid(format!("expected 9 {} facelets but got {}", face.letter(), count))); }
+        }
+
+        let mut state = Self::solved();
+        let mut seen = Vec::new();
+
+        for (position, faces) in piece_positions() {
+
+            let pieceColours: Vec<Face> = faces.iter().map(|face| colours[slot_index(position, *face)]).collect();
+            let name: String = faces.iter().map(|face| face.letter()).collect();
+
+            //Stickers of a piece must be on perpendicular faces, and go around a corner the same way they do on the solved cube
+            let perpendicular = pieceColours.iter().enumerate()
+                .all(|(i, a)| pieceColours[i + 1..].iter().all(|b| dot(a.normal(), b.normal()) == 0));
+            let mirrored = faces.len() == 3 && det(faces[0].normal(), faces[1].normal(), faces[2].normal())
+                != det(pieceColours[0].normal(), pieceColours[1].normal(), pieceColours[2].normal());
+
+            if !perpendicular || mirrored {
+                let piece: String = pieceColours.iter().map(|colour| colour.letter()).collect();
+                return Err(invalid(format!("no piece has the colours {} (at {})", piece, name)));
+            }
+
+            let home = pieceColours.iter().fold([0, 0, 0], |home, colour| array_from_fn(|i| home[i] + colour.normal()[i]));
+            if seen.contains(&home) { return Err(invalid(format!("the piece at {} appears more than once", name))); }
+            seen.push(home);
+
+            for (face, colour) in faces.iter().zip(&pieceColours) {
+                state.stickers[slot_index(position, *face)] = slot_index(home, *colour) as u8;
+            }
+        }
+
+        //Opposite centers must be opposite colours and go around the cube the same way as on the solved cube
+        let center = |face: Face| colours[slot_index(face.normal(), face)].normal();
+        if (0..3).any(|i| dot(center(Face::ALL[i]), center(Face::ALL[i + 3])) != -1)
+            || det(center(Face::U), center(Face::R), center(Face::F)) != det(Face::U.normal(), Face::R.normal(), Face::F.normal())
+        {
+            return Err(invalid("the centers are not arranged as on a real cube".to_string()));
+        }
+
+        state.check_solvable()?;
+        Ok(state)
+    }
+
+    // Check the twist of the corners, flip of the edges and permutation parity are those of a cube which could be solved
+    fn check_solvable(&self) -> Result<(), RubixError>
+    {
+        //Turn the whole cube so the centers are at home, leaving only turns of the outer faces to solve it
+        let upright = orientations_of(self).into_iter()
+            .find(|state| Face::ALL.iter().all(|face| state.sticker_face(slot_index(face.normal(), *face)) == *face))
+            .unwrap_or_else(|| self.clone());
+
+        let (mut twist, mut flips) = (0, 0);
+        let (mut corners, mut edges) = (Vec::new(), Vec::new());
+
+        for (position, faces) in piece_positions() {
+
+            let slotFace = |face: Face| upright.sticker_face(slot_index(position, face));
+            let (home, _) = slot_position(upright.sticker(slot_index(position, faces[0])));
+
+            match faces.len() {
+                3 => {
+                    //Corners are twisted by how far their U or D sticker is from the U or D face, going counter-clockwise
+                    let first = faces.iter().copied().find(|face| matches!(face, Face::U | Face::D)).unwrap_or(faces[0]);
+                    let others: Vec<Face> = faces.iter().copied().filter(|face| *face != first).collect();
+                    let order = if det(first.normal(), others[0].normal(), others[1].normal()) == 1 { [first, others[0], others[1]] }
+                                else { [first, others[1], others[0]] };
+
+                    twist += order.iter().position(|face| matches!(slotFace(*face), Face::U | Face::D)).unwrap_or(0);
+                    corners.push((position, home));
+                },
+                2 => {
+                    //Edges are flipped if their U or D sticker (else F or B sticker) isn't on the U or D face (else F or B face)
+                    let primary = |pieceFaces: &[Face]| pieceFaces.iter().copied()
+                        .find(|face| matches!(face, Face::U | Face::D))
+                        .or_else(|| pieceFaces.iter().copied().find(|face| matches!(face, Face::F | Face::B)));
+
+                    let pieceColours: Vec<Face> = faces.iter().map(|face| slotFace(*face)).collect();
+                    if let (Some(face), Some(colour)) = (primary(&faces), primary(&pieceColours)) {
+                        if slotFace(face) != colour { flips += 1; }
+                    }
+                    edges.push((position, home));
+                },
+                _ => (),
+            }
+        }
+
+        if twist % 3 != 0 { return Err(RubixError::InvalidState("a corner is twisted".to_string())); }
+        if flips % 2 != 0 { return Err(RubixError::InvalidState("an edge is flipped".to_string())); }
+        if parity(&corners) != parity(&edges) { return Err(RubixError::InvalidState("two pieces are swapped".to_string())); }
+
+        Ok(())
+    }
+
+    /*
+        Method to find the cubie at a position within the cube. Returns the home position of that cubie
+        along with the rotation that takes it from its home orientation to its current one
+    */
+    pub fn cubie_at(&self, position: IVec3) -> (IVec3, Rotation)
+    {
+        //Pairs of (home direction, current direction) from which the rotation of the cubie can be built
+        let mut pairs = Vec::new();
+        let mut home = [0, 0, 0];
+
+        for face in Face::ALL.into_iter().filter(|face| dot(position, face.normal()) == 1) {
+
+            let (homePosition, homeFace) = slot_position(self.sticker(slot_index(position, face)));
+            home = homePosition;
+            pairs.push((homeFace.normal(), face.normal()));
+
+            //Center pieces have a single sticker so use their twist for the second direction
+            if homePosition == homeFace.normal() {
+                let twist = self.center_twist(homeFace);
+                pairs.push((homeFace.up(), (0..twist).fold(face.up(), |up, _| quarter_turn(up, negate(face.normal())))));
+            }
+        }
+
+        //The core never moves relative to the other cubies drawn
+        if pairs.len() < 2 { return (home, [[1, 0, 0], [0, 1, 0], [0, 0, 1]]); }
+
+        let (a1, b1) = pairs[0];
+        let (a2, b2) = pairs[1];
+        let (a3, b3) = (cross(a1, a2), cross(b1, b2));
+
+        //R = B * A^T since the home directions form an orthonormal basis
+        let rotation = std::array::from_fn(|r| std::array::from_fn(|c| a1[c] * b1[r] + a2[c] * b2[r] + a3[c] * b3[r]));
+
+        (home, rotation)
+    }
+}
+
+// Quarter turns clockwise from the up direction of a face to the given direction lying on that face
+fn twist_between(face: Face, direction: IVec3) -> u8
+{
+    let mut up = face.up();
+
+    for twist in 0..4 {
+        if up == direction { return twist; }
+        up = quarter_turn(up, negate(face.normal()));
+    }
+
+    unreachable!("direction does not lie on face")
+}
+
+// Get whether a permutation (given as pairs of current and home positions) is odd
+fn parity(pieces: &[(IVec3, IVec3)]) -> bool
+{
+    let mut visited = vec![false; pieces.len()];
+    let mut odd = false;
+
+    for start in 0..pieces.len() {
+
+        let mut i = start;
+        while !visited[i] {
+            visited[i] = true;
+
+            //Follow the cycle to wherever the piece here belongs, each step of which is a swap
+            i = pieces.iter().position(|(position, _)| *position == pieces[i].1).unwrap_or(start);
+            if !visited[i] { odd = !odd; }
+        }
+    }
+
+    odd
+}
+
+// Get the 24 states reachable from solved by rotating the whole cube
+pub fn whole_cube_orientations() -> Vec<CubeState>
+{
+    orientations_of(&CubeState::solved())
+}
+
+// Get the 24 states a state can be turned into by rotating the whole cube
+fn orientations_of(state: &CubeState) -> Vec<CubeState>
+{
+    let mut orientations = vec![state.clone()];
+    let mut i = 0;
+
+    while i < orientations.len() {
+
+        for face in [Face::R, Face::U, Face::F] {
+
+            let mut next = orientations[i].clone();
+            next.apply_turn(face, [true; 3], 1);
+
+            if !orientations.contains(&next) { orientations.push(next); }
+        }
+        i += 1;
+    }
+
+    orientations
+}
@@ -0,0 +1,506 @@
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::hash::Hash;
+use std::sync::OnceLock;
+
+use wasm_bindgen::prelude::*;
+
+use crate::blind::reference_face;
+use crate::error::RubixError;
+use crate::metrics::Metrics;
+use crate::notation::{format_moves, Layers, Move, Slice};
+use crate::simplify::simplify;
+use crate::state::{slot_index, whole_cube_orientations, CubeState, Face, IVec3};
+
+/*
+    Step solvers for Roux (first block, second block, CMLL and LSE) and ZZ (EOLine, EOCross and ZZ-F2L).
+    Each step is solved in stages (eg. a block's square then its pair), each found with an iterative deepening search
+    (IDA*) over the moves the step is usually done with, keeping everything solved by the steps before it. Only the stickers
+    a stage cares about are followed, one per piece (which is enough to know where the piece is and how it is twisted), and
+    the search is pruned with tables of how far those are from solved, made the first time a step is solved.
+    Steps are solved relative to how the cube is held: by the centers for the first step of a method, otherwise by
+    the pieces the steps before it solved (so a Roux M slice which is out doesn't matter)
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const STEP_SOLUTION: &'static str = r#"
+/** Step of Roux ("firstBlock", "secondBlock", "cmll", "lse") or ZZ ("eoLine", "eoCross", "zzF2l") to solve */
+export type SolverStep = "firstBlock" | "secondBlock" | "cmll" | "lse" | "eoLine" | "eoCross" | "zzF2l";
+
+/** Moves which solve a step from the current state */
+export interface StepSolution {
+    step: SolverStep;
+    moves: string;
+    moveCount: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "SolverStep")]
+    pub type SolverStepName;
+
+    #[wasm_bindgen(typescript_type = "StepSolution | undefined")]
+    pub type StepSolutionObject;
+}
+
+// Deepest search allowed for a stage of a step (in moves), so a search can't hang the page
+pub const MAX_STEP_DEPTH: usize = 20;
+
+// Most positions a pruning table is allowed to hold before it stops being filled in
+const TABLE_LIMIT: usize = 400_000;
+
+// Pieces by the faces they are on, with the first face the sticker followed
+type Piece = &'static [Face];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Target {
+    Piece(Piece),     // Piece in its home position, the right way round
+    Center(Face),     // Center (which slices move) on its home face
+    Oriented(Piece),  // Piece oriented (see reference_face) in any of the positions of the oriented pieces followed with it
+}
+
+// Roux blocks and the pieces left for CMLL and LSE
+const FIRST_SQUARE: [Target; 4] = [Target::Center(Face::L), Target::Piece(&[Face::D, Face::L]), Target::Piece(&[Face::L, Face::B]), Target::Piece(&[Face::D, Face::L, Face::B])];
+const FIRST_PAIR: [Target; 2] = [Target::Piece(&[Face::D, Face::F, Face::L]), Target::Piece(&[Face::L, Face::F])];
+const SECOND_SQUARE: [Target; 4] = [Target::Center(Face::R), Target::Piece(&[Face::D, Face::R]), Target::Piece(&[Face::R, Face::B]), Target::Piece(&[Face::D, Face::B, Face::R])];
+const SECOND_PAIR: [Target; 2] = [Target::Piece(&[Face::D, Face::R, Face::F]), Target::Piece(&[Face::R, Face::F])];
+
+const LAST_LAYER_CORNERS: [Target; 4] = [
+    Target::Piece(&[Face::U, Face::R, Face::F]), Target::Piece(&[Face::U, Face::F, Face::L]),
+    Target::Piece(&[Face::U, Face::L, Face::B]), Target::Piece(&[Face::U, Face::B, Face::R]),
+];
+const SIX_EDGES: [Target; 10] = [
+    Target::Center(Face::U), Target::Center(Face::F), Target::Center(Face::D), Target::Center(Face::B),
+    Target::Piece(&[Face::U, Face::F]), Target::Piece(&[Face::U, Face::B]), Target::Piece(&[Face::U, Face::L]),
+    Target::Piece(&[Face::U, Face::R]), Target::Piece(&[Face::D, Face::F]), Target::Piece(&[Face::D, Face::B]),
+];
+
+// ZZ edge orientation (by the F and B faces), line and cross
+const EDGES: [Piece; 12] = [
+    &[Face::U, Face::F], &[Face::U, Face::B], &[Face::U, Face::L], &[Face::U, Face::R],
+    &[Face::D, Face::F], &[Face::D, Face::B], &[Face::D, Face::L], &[Face::D, Face::R],
+    &[Face::F, Face::L], &[Face::F, Face::R], &[Face::B, Face::L], &[Face::B, Face::R],
+];
+const LINE: [Target; 2] = [Target::Piece(&[Face::D, Face::F]), Target::Piece(&[Face::D, Face::B])];
+const CROSS_SIDES: [Target; 2] = [Target::Piece(&[Face::D, Face::L]), Target::Piece(&[Face::D, Face::R])];
+
+// Moves each step is solved with
+const FACES: [Layers; 6] = [Layers::Face(Face::U), Layers::Face(Face::R), Layers::Face(Face::F), Layers::Face(Face::D), Layers::Face(Face::L), Layers::Face(Face::B)];
+const FIRST_BLOCK_MOVES: [Layers; 7] = [Layers::Face(Face::U), Layers::Face(Face::R), Layers::Face(Face::F), Layers::Face(Face::D), Layers::Face(Face::L), Layers::Face(Face::B), Layers::Slice(Slice::M)];
+const SECOND_BLOCK_MOVES: [Layers; 4] = [Layers::Face(Face::R), Layers::Wide(Face::R), Layers::Face(Face::U), Layers::Slice(Slice::M)];
+const CMLL_MOVES: [Layers; 4] = [Layers::Face(Face::R), Layers::Face(Face::U), Layers::Face(Face::F), Layers::Face(Face::L)];
+const LSE_MOVES: [Layers; 2] = [Layers::Slice(Slice::M), Layers::Face(Face::U)];
+const ZZ_F2L_MOVES: [Layers; 3] = [Layers::Face(Face::R), Layers::Face(Face::U), Layers::Face(Face::L)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step { FirstBlock, SecondBlock, Cmll, Lse, EoLine, EoCross, ZzF2l }
+
+impl Step {
+
+    const ALL: [Step; 7] = [Step::FirstBlock, Step::SecondBlock, Step::Cmll, Step::Lse, Step::EoLine, Step::EoCross, Step::ZzF2l];
+
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        Self::ALL.into_iter().find(|step| step.name() == name)
+    }
+
+    pub fn name(self) -> &'static str
+    {
+        match self {
+            Step::FirstBlock  => "firstBlock",
+            Step::SecondBlock => "secondBlock",
+            Step::Cmll        => "cmll",
+            Step::Lse         => "lse",
+            Step::EoLine      => "eoLine",
+            Step::EoCross     => "eoCross",
+            Step::ZzF2l       => "zzF2l",
+        }
+    }
+
+    // Deepest each stage of the step is searched unless another depth is given
+    pub fn default_depth(self) -> usize
+    {
+        match self {
+            Step::FirstBlock | Step::SecondBlock | Step::ZzF2l => 10,
+            Step::Cmll                                         => 16,
+            Step::Lse | Step::EoLine | Step::EoCross           => 20,
+        }
+    }
+
+    // Step which must be solved before this one
+    fn after(self) -> Option<Step>
+    {
+        match self {
+            Step::SecondBlock => Some(Step::FirstBlock),
+            Step::Cmll        => Some(Step::SecondBlock),
+            Step::Lse         => Some(Step::Cmll),
+            Step::ZzF2l       => Some(Step::EoLine),
+            _ => None,
+        }
+    }
+
+    fn moves(self) -> &'static [Layers]
+    {
+        match self {
+            Step::FirstBlock             => &FIRST_BLOCK_MOVES,
+            Step::SecondBlock            => &SECOND_BLOCK_MOVES,
+            Step::Cmll                   => &CMLL_MOVES,
+            Step::Lse                    => &LSE_MOVES,
+            Step::EoLine | Step::EoCross => &FACES,
+            Step::ZzF2l                  => &ZZ_F2L_MOVES,
+        }
+    }
+
+    // What each stage of the step solves, in order
+    fn stages(self) -> Vec<Vec<Target>>
+    {
+        let orientation = EDGES.map(Target::Oriented).to_vec();
+
+        match self {
+            Step::FirstBlock  => vec![FIRST_SQUARE.to_vec(), FIRST_PAIR.to_vec()],
+            Step::SecondBlock => vec![SECOND_SQUARE.to_vec(), SECOND_PAIR.to_vec()],
+            Step::Cmll        => vec![LAST_LAYER_CORNERS.to_vec()],
+            Step::Lse         => vec![SIX_EDGES.to_vec()],
+            Step::EoLine      => vec![[orientation, LINE.to_vec()].concat()],
+            Step::EoCross     => vec![[orientation, LINE.to_vec(), CROSS_SIDES.to_vec()].concat()],
+            //ZZ blocks are the same as Roux's but without the centers (which are never moved)
+            Step::ZzF2l       => vec![FIRST_SQUARE[1..].to_vec(), FIRST_PAIR.to_vec(), SECOND_SQUARE[1..].to_vec(), SECOND_PAIR.to_vec()],
+        }
+    }
+
+    // Everything solved by the steps before this one
+    fn solved_before(self) -> Vec<Target>
+    {
+        let mut targets = Vec::new();
+        let mut step = self.after();
+
+        while let Some(before) = step {
+            targets.extend(before.stages().concat());
+            step = before.after();
+        }
+        targets
+    }
+}
+
+/*
+ * Find moves which solve a step from a state, searching each of its stages up to a number of moves deep.
+ * Gives None if a stage can't be solved within that many moves, or a RubixError if the steps before it aren't solved
+ */
+pub fn solve_step(state: &CubeState, step: Step, maxDepth: usize) -> Result<Option<StepSolution>, RubixError>
+{
+    let before = step.solved_before();
+    let frame = frame(state, &before, step.after().is_some())
+        .ok_or_else(|| RubixError::InvalidState(format!("{} must be solved before {}", step.after().map_or("", Step::name), step.name())))?;
+
+    //Stickers are followed by where they are in the state, so a stage starts from where the last one finished
+    let mut positions: Vec<u8> = vec![0; 54];
+    for slot in 0..54 { positions[state.sticker(slot)] = slot as u8; }
+
+    let mut solution = Vec::new();
+
+    for search in searches(step) {
+        let Some(moves) = search.solve(&positions, &frame, maxDepth) else { return Ok(None); };
+
+        for m in &moves {
+            let dest = destinations(*m);
+            for position in positions.iter_mut() { *position = dest[*position as usize]; }
+        }
+        solution.extend(moves);
+    }
+
+    Ok(Some(StepSolution { step, moves: simplify(&solution) }))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepSolution {
+    pub step: Step,
+    pub moves: Vec<Move>,
+}
+
+impl StepSolution {
+
+    // Method to build the plain JS object given to JS (see StepSolution)
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("step", JsValue::from_str(self.step.name()));
+        set("moves", JsValue::from_str(&format_moves(&self.moves)));
+        set("moveCount", JsValue::from(Metrics::of(&self.moves).htm));
+
+        object.into()
+    }
+}
+
+/*
+    Orientation the cube is solved in, as the home slot (in the state) of the sticker which belongs in each slot.
+    This is the whole cube orientation with the most of what the steps before solved (up to a turn of U, as CMLL can be
+    left for LSE to line up), then the most centers in place. Gives None if what should already be solved isn't
+*/
+fn frame(state: &CubeState, before: &[Target], required: bool) -> Option<Vec<usize>>
+{
+    let mut positions = vec![0; 54];
+    for slot in 0..54 { positions[state.sticker(slot)] = slot; }
+
+    let scored = whole_cube_orientations().into_iter().map(|orientation| {
+        let frame: Vec<usize> = (0..54).map(|slot| orientation.sticker(slot)).collect();
+
+        //Turning U doesn't move anything solved before except the last layer corners, which are only solved up to a turn of U
+        let solved = (0..4).map(|turns| {
+            let dest = destinations(Move::new(Layers::Face(Face::U), turns));
+            before.iter().filter(|target| is_solved(**target, before, |home| dest[positions[frame[home]]] as usize)).count()
+        }).max().unwrap_or(0);
+
+        let centers = Face::ALL.into_iter().filter(|face| positions[frame[face.index() * 9 + 4]] == face.index() * 9 + 4).count();
+        (solved, centers, frame)
+    });
+
+    let (solved, _, frame) = scored.max_by_key(|(solved, centers, _)| (*solved, *centers))?;
+    (!required || solved == before.len()).then_some(frame)
+}
+
+// Check a target is solved, given where the sticker which belongs in each slot is
+fn is_solved(target: Target, targets: &[Target], position: impl Fn(usize) -> usize) -> bool
+{
+    match target {
+        Target::Oriented(_) => {
+            //Oriented pieces can be in any of the slots the others followed with them are oriented in
+            let slots: Vec<usize> = targets.iter().filter(|other| matches!(other, Target::Oriented(_))).map(|other| followed(*other)).collect();
+            slots.contains(&position(followed(target)))
+        },
+        _ => position(followed(target)) == followed(target),
+    }
+}
+
+// Get the slot of the sticker followed for a target
+fn followed(target: Target) -> usize
+{
+    match target {
+        Target::Piece(faces)    => slot_index(position(faces), faces[0]),
+        Target::Center(face)    => face.index() * 9 + 4,
+        Target::Oriented(faces) => slot_index(position(faces), reference_face(position(faces))),
+    }
+}
+
+fn position(faces: &[Face]) -> IVec3
+{
+    faces.iter().fold([0, 0, 0], |position, face| std::array::from_fn(|i| position[i] + face.normal()[i]))
+}
+
+// Get the slot each slot's sticker goes to with a move
+fn destinations(m: Move) -> [u8; 54]
+{
+    let state = CubeState::solved().apply_moves(&[m]);
+
+    let mut dest = [0; 54];
+    for slot in 0..54 { dest[state.sticker(slot)] = slot as u8; }
+    dest
+}
+
+/*
+    Search for one stage of a step. Pieces followed are packed 6 bits a slot: where each piece which has to be in its home
+    position is, then (as a bit mask) which slots the pieces which only have to be oriented are in
+*/
+struct Search {
+    moves: Vec<Move>,
+    destinations: Vec<[u8; 54]>,
+    axes: Vec<(usize, usize)>, // Axis each move turns around, and its layer's index in the step's moves (so turns of the same axis are only searched in one order)
+    homes: Vec<usize>,         // Slots the pieces which must be home are followed by
+    oriented: u64,             // Slots the oriented pieces must fill
+    homeTable: HashMap<u128, u8>,
+    orientedTable: HashMap<u64, u8>,
+    pruned: (u8, u8),          // Depths the tables are filled to, so anything not in them is further than that
+}
+
+impl Search {
+
+    fn new(moves: &[Layers], targets: &[Target]) -> Self
+    {
+        let moves: Vec<Move> = moves.iter().flat_map(|layers| (1..4).map(|turns| Move::new(*layers, turns))).collect();
+        let destinations: Vec<[u8; 54]> = moves.iter().map(|m| destinations(*m)).collect();
+
+        let axes = moves.iter().map(|m| {
+            let (face, _) = m.turn();
+            (face.normal().iter().position(|n| *n != 0).unwrap_or(0), moves.iter().position(|other| other.layers == m.layers).unwrap_or(0))
+        }).collect();
+
+        //Stickers no move turns are left out, as they can't be solved (or unsolved) by this stage
+        let moved = |slot: &usize| destinations.iter().any(|dest| dest[*slot] as usize != *slot);
+
+        let homes: Vec<usize> = targets.iter().filter(|target| !matches!(target, Target::Oriented(_))).map(|target| followed(*target)).filter(moved).collect();
+        let oriented = targets.iter().filter(|target| matches!(target, Target::Oriented(_))).map(|target| followed(*target)).filter(moved)
+            .fold(0, |mask, slot| mask | 1 << slot);
+
+        let (homeTable, homeDepth) = distances(pack(&homes), &destinations, |key, dest| turn_packed(key, homes.len(), dest));
+        let (orientedTable, orientedDepth) = distances(oriented, &destinations, turn_mask);
+
+        Self { moves, destinations, axes, homes, oriented, homeTable, orientedTable, pruned: (homeDepth, orientedDepth) }
+    }
+
+    // Find the moves which solve the stage, given where the sticker from each slot is and the orientation it is solved in
+    fn solve(&self, positions: &[u8], frame: &[usize], maxDepth: usize) -> Option<Vec<Move>>
+    {
+        let home = pack(&self.homes.iter().map(|slot| positions[frame[*slot]] as usize).collect::<Vec<_>>());
+        let oriented = (0..54).filter(|slot| self.oriented & 1 << slot != 0).fold(0, |mask, slot| mask | 1 << positions[frame[slot]]);
+
+        let mut path = Vec::new();
+        (0..=maxDepth.min(MAX_STEP_DEPTH)).find(|depth| self.search(home, oriented, *depth, &mut path))?;
+
+        Some(path.into_iter().map(|i| self.moves[i]).collect())
+    }
+
+    fn search(&self, home: u128, oriented: u64, depth: usize, path: &mut Vec<usize>) -> bool
+    {
+        let estimate = self.estimate(home, oriented);
+        if estimate > depth { return false; }
+        if estimate == 0 { return true; }
+
+        for i in 0..self.moves.len() {
+
+            //Moves which turn the same layers as the last (or those around the same axis, out of order) have been searched already
+            if let Some(last) = path.last().map(|last| self.axes[*last]) {
+                if last.0 == self.axes[i].0 && last.1 >= self.axes[i].1 { continue; }
+            }
+
+            path.push(i);
+            let dest = &self.destinations[i];
+            if self.search(turn_packed(home, self.homes.len(), dest), turn_mask(oriented, dest), depth - 1, path) { return true; }
+            path.pop();
+        }
+
+        false
+    }
+
+    // Fewest moves the pieces could be solved in (which is only 0 when they are)
+    fn estimate(&self, home: u128, oriented: u64) -> usize
+    {
+        let home = self.homeTable.get(&home).copied().unwrap_or(self.pruned.0 + 1);
+        let oriented = self.orientedTable.get(&oriented).copied().unwrap_or(self.pruned.1 + 1);
+        home.max(oriented) as usize
+    }
+}
+
+// Get the searches for each stage of a step, made the first time the step is solved
+fn searches(step: Step) -> &'static [Search]
+{
+    static SEARCHES: [OnceLock<Vec<Search>>; 7] = [const { OnceLock::new() }; 7];
+
+    let index = Step::ALL.iter().position(|other| *other == step).unwrap_or(0);
+    SEARCHES[index].get_or_init(|| {
+        let mut targets = step.solved_before();
+
+        step.stages().into_iter().map(|stage| {
+            //Edge orientation solved before is kept by the moves ZZ-F2L is solved with, so doesn't need following
+            targets.retain(|target| !matches!(target, Target::Oriented(_)));
+            targets.extend(stage);
+            Search::new(step.moves(), &targets)
+        }).collect()
+    })
+}
+
+// Fill in how many moves positions are from solved, going out from solved until there are too many to keep
+fn distances<K: Copy + Eq + Hash>(solved: K, destinations: &[[u8; 54]], turn: impl Fn(K, &[u8; 54]) -> K) -> (HashMap<K, u8>, u8)
+{
+    let mut table = HashMap::from([(solved, 0)]);
+    let mut frontier = vec![solved];
+    let mut depth = 0;
+
+    while !frontier.is_empty() && table.len() + frontier.len() * destinations.len() <= TABLE_LIMIT {
+        depth += 1;
+        let mut next = Vec::new();
+
+        for key in &frontier {
+            for dest in destinations {
+                let turned = turn(*key, dest);
+                if let Entry::Vacant(entry) = table.entry(turned) {
+                    entry.insert(depth);
+                    next.push(turned);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    //Once every position is in the table anything else can't be solved, which is further than any search goes
+    let depth = if frontier.is_empty() { MAX_STEP_DEPTH as u8 } else { depth };
+    (table, depth)
+}
+
+fn pack(slots: &[usize]) -> u128
+{
+    slots.iter().fold(0, |key, slot| key << 6 | *slot as u128)
+}
+
+fn turn_packed(key: u128, count: usize, dest: &[u8; 54]) -> u128
+{
+    (0..count).rev().fold(0, |turned, i| turned << 6 | dest[(key >> (i * 6) & 63) as usize] as u128)
+}
+
+fn turn_mask(mask: u64, dest: &[u8; 54]) -> u64
+{
+    (0..54).filter(|slot| mask & 1 << slot != 0).fold(0, |turned, slot| turned | 1 << dest[slot])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_moves;
+    use crate::scramble::{random_scramble, seeded_random};
+
+    // Solve a step, checking it is solved (along with the steps before it) afterwards
+    fn solved_step(state: &CubeState, step: Step) -> CubeState
+    {
+        let solution = solve_step(state, step, step.default_depth()).unwrap().unwrap_or_else(|| panic!("{} not found", step.name()));
+        let solved = state.apply_moves(&solution.moves);
+
+        let targets = [step.solved_before(), step.stages().concat()].concat();
+        assert!(frame(&solved, &targets, true).is_some(), "{} not solved by {}", step.name(), format_moves(&solution.moves));
+        solved
+    }
+
+    #[test]
+    fn roux_stages_reach_their_targets()
+    {
+        let mut random = seeded_random(49);
+
+        for _ in 0..3 {
+            let mut state = CubeState::solved().apply_moves(&random_scramble(20, &mut random));
+
+            for step in [Step::FirstBlock, Step::SecondBlock, Step::Cmll, Step::Lse] {
+                state = solved_step(&state, step);
+            }
+            assert!(state.is_solved(false));
+        }
+    }
+
+    #[test]
+    fn zz_stages_reach_their_targets()
+    {
+        let mut random = seeded_random(50);
+
+        for _ in 0..3 {
+            let state = CubeState::solved().apply_moves(&random_scramble(20, &mut random));
+
+            solved_step(&solved_step(&state, Step::EoLine), Step::ZzF2l);
+            solved_step(&state, Step::EoCross);
+        }
+    }
+
+    #[test]
+    fn steps_need_the_steps_before_them()
+    {
+        let scrambled = CubeState::solved().apply_moves(&parse_moves("R U F' L2 D B'").unwrap());
+
+        for step in [Step::SecondBlock, Step::Cmll, Step::Lse, Step::ZzF2l] {
+            assert!(solve_step(&scrambled, step, 1).is_err(), "{}", step.name());
+        }
+
+        //Nothing to do on a solved cube, however it is held
+        let rotated = CubeState::solved().apply_moves(&parse_moves("x y").unwrap());
+        for step in Step::ALL {
+            assert_eq!(solve_step(&rotated, step, 0).unwrap().map(|solution| solution.moves), Some(Vec::new()), "{}", step.name());
+        }
+    }
+}
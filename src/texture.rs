@@ -1,35 +1,256 @@
 
-use wasm_bindgen::prelude::*;
-use web_sys::{WebGl2RenderingContext, HtmlImageElement};
+use web_sys::{WebGl2RenderingContext, WebGlTexture};
+use image::{imageops, Rgba, RgbaImage};
+use base64::Engine;
+
+use crate::error::RubixError;
+use crate::state::{dot, Face, IVec3};
+
+/*
+    Layout of the texture atlas, which decides which tile of the atlas is drawn on each side of each cubie
+*/
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AtlasLayout {
+    Colours,  // One tile per colour and every cubie has all 6 colours
+    Pictures, // Every face picture split into 3x3 tiles, plus a black tile for the sides hidden inside the cube
+}
+
+impl AtlasLayout {
+
+    // Number of columns and rows of tiles in the atlas
+    pub fn size(self) -> (u32, u32)
+    {
+        match self {
+            AtlasLayout::Colours  => (6, 1),
+            AtlasLayout::Pictures => (6 * 3 + 1, 3),
+        }
+    }
+
+    // Get the column and row (from the top) of the tile drawn on a side of the cubie which started at the home position
+    pub fn tile(self, home: IVec3, face: Face) -> (u32, u32)
+    {
+        match self {
+            AtlasLayout::Colours => (face.colour_index(), 0),
+
+            //Sides on the outside of the cube show the part of the picture where the cubie started out
+            AtlasLayout::Pictures if dot(home, face.normal()) == 1 => {
+                let col = face.colour_index() * 3 + (1 + dot(home, face.right())) as u32;
+                let row = (1 - dot(home, face.up())) as u32;
+                (col, row)
+            },
+            AtlasLayout::Pictures => (6 * 3, 0),
+        }
+    }
+}
+
+/*
+    Description of the sticker atlas drawn procedurally in rust (one square tile per sticker colour, left to right)
+*/
+pub struct StickerAtlas {
+    pub colours: [[u8; 4]; 6], // Red 0, Orange 1, Yellow 2, Green 3, Blue 4, White 5
+    pub tileSize: u32,         // Width & height in pixels of a single sticker tile
+    pub borderWidth: u32,      // Width in pixels of the black border around each sticker
+    pub cornerRadius: u32,     // Radius in pixels of the rounded corners of each sticker
+}
+
+impl Default for StickerAtlas {
+
+    fn default() -> Self
+    {
+        Self {
+            colours: [
+                [183,  18,  52, 255], //Red
+                [255,  88,   0, 255], //Orange
+                [255, 213,   0, 255], //Yellow
+                [  0, 155,  72, 255], //Green
+                [  0,  70, 173, 255], //Blue
+                [255, 255, 255, 255], //White
+            ],
+            tileSize: 64,
+            borderWidth: 4,
+            cornerRadius: 10,
+        }
+    }
+}
+
+impl StickerAtlas {
+
+    // Create atlas from css style hex colours (ie. "#rrggbb") given in the order red, orange, yellow, green, blue, white
+    pub fn from_hex_colours(hexColours: &[String]) -> Result<Self, RubixError>
+    {
+        let mut atlas = Self::default();
+
+        if hexColours.len() != atlas.colours.len() {
+            return Err(RubixError::Texture(format!("Expected {} sticker colours but got {}", atlas.colours.len(), hexColours.len())));
+        }
+
+        for (colour, hex) in atlas.colours.iter_mut().zip(hexColours) {
+            *colour = parse_hex_colour(hex).ok_or_else(|| RubixError::Texture(format!("Invalid sticker colour: {}", hex)))?;
+        }
+
+        Ok(atlas)
+    }
+
+    // Method to draw the atlas into an image buffer
+    pub fn build(&self) -> RgbaImage
+    {
+        let size = self.tileSize;
+        let black = Rgba([0, 0, 0, 255]);
+
+        RgbaImage::from_fn(size * self.colours.len() as u32, size, |x, y| {
+
+            //Position of pixel within its own tile
+            let (tx, ty) = (x % size, y % size);
+
+            if self.in_sticker(tx, ty) { Rgba(self.colours[(x / size) as usize]) } else { black }
+        })
+    }
+
+    // Method to check whether a pixel of a tile lies on the rounded sticker (ie. not on the black border)
+    fn in_sticker(&self, x: u32, y: u32) -> bool
+    {
+        let lo = self.borderWidth as f32;
+        let hi = (self.tileSize - self.borderWidth) as f32;
+        let radius = self.cornerRadius as f32;
+
+        //Sample pixel at its center
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+        if px < lo || px > hi || py < lo || py > hi { return false; }
+
+        //Distance from pixel to the nearest corner circle center (only non zero when inside a corner region)
+        let dx = (lo + radius - px).max(px - (hi - radius)).max(0.0);
+        let dy = (lo + radius - py).max(py - (hi - radius)).max(0.0);
+
+        dx * dx + dy * dy <= radius * radius
+    }
+}
 
 /*
-    Function that loads a texture from an html img tag on the client side
+    Pictures printed across each face of the cube, which are split over the 9 stickers of that face
 */
-pub fn load_texture_from_htmlImg(gl: &WebGl2RenderingContext, htmlImg: &HtmlImageElement, textureNumber: u32) -> Result<(), JsValue>
+pub struct PictureAtlas {
+    pub pictures: Vec<RgbaImage>, // In the same order as sticker colours ie. red, orange, yellow, green, blue, white
+    pub tileSize: u32,
+    pub borderWidth: u32,
+}
+
+impl PictureAtlas {
+
+    // Create atlas from 6 images given as data urls (eg. "data:image/png;base64,...") or plain base64 strings
+    pub fn from_data_urls(dataUrls: &[String]) -> Result<Self, RubixError>
+    {
+        if dataUrls.len() != 6 {
+            return Err(RubixError::Texture(format!("Expected 6 face images but got {}", dataUrls.len())));
+        }
+
+        let pictures = dataUrls.iter().map(|url| decode_data_url(url)).collect::<Result<Vec<_>, _>>()?;
 
+        Ok(Self { pictures, tileSize: 64, borderWidth: 2 })
+    }
+
+    // Method to draw the atlas into an image buffer
+    pub fn build(&self) -> RgbaImage
+    {
+        let size = self.tileSize;
+        let (cols, rows) = AtlasLayout::Pictures.size();
+        let mut atlas = RgbaImage::from_pixel(cols * size, rows * size, Rgba([0, 0, 0, 255]));
+
+        for (i, picture) in self.pictures.iter().enumerate() {
+
+            //Scale picture to cover a 3x3 block of tiles then copy it into place
+            let scaled = imageops::resize(picture, 3 * size, 3 * size, imageops::FilterType::Triangle);
+            imageops::replace(&mut atlas, &scaled, (i as u32 * 3 * size) as i64, 0);
+        }
+
+        //Draw a thin black border around every sticker (also stops neighbouring faces bleeding into each other)
+        for (x, y, pixel) in atlas.enumerate_pixels_mut() {
+            let (tx, ty) = (x % size, y % size);
+            let border = self.borderWidth;
+
+            if tx < border || ty < border || tx >= size - border || ty >= size - border {
+                *pixel = Rgba([0, 0, 0, 255]);
+            }
+        }
+
+        atlas
+    }
+}
+
+// Decode an image from a data url or plain base64 string
+fn decode_data_url(dataUrl: &str) -> Result<RgbaImage, RubixError>
+{
+    //Anything before the comma of a data url is just its media type
+    let encoded = dataUrl.split_once(',').map_or(dataUrl, |(_, data)| data);
+
+    let bytes = base64::engine::general_purpose::STANDARD.decode(encoded.trim())
+        .map_err(|e| RubixError::Texture(format!("Unable to decode face image base64: {}", e)))?;
+
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| RubixError::Texture(format!("Unable to decode face image: {}", e)))?;
+
+    Ok(image.to_rgba8())
+}
+
+/*
+    Function to parse a css style hex colour of the form "#rrggbb" or "#rrggbbaa"
+*/
+pub fn parse_hex_colour(hex: &str) -> Option<[u8; 4]>
+{
+    let digits = hex.trim().strip_prefix('#')?;
+
+    if !(digits.len() == 6 || digits.len() == 8) || !digits.is_ascii() { return None; }
+
+    let mut colour = [255; 4];
+    for (i, channel) in colour.iter_mut().enumerate().take(digits.len() / 2) {
+        *channel = u8::from_str_radix(&digits[2*i..2*i + 2], 16).ok()?;
+    }
+
+    Some(colour)
+}
+
+/*
+    Function that decodes png bytes (eg. embedded with include_bytes!) into an image buffer
+*/
+pub fn decode_png_bytes(pngBytes: &[u8]) -> Result<RgbaImage, RubixError>
+{
+    let image = image::load_from_memory_with_format(pngBytes, image::ImageFormat::Png)
+        .map_err(|e| RubixError::Texture(format!("Unable to decode texture image: {}", e)))?;
+
+    Ok(image.to_rgba8())
+}
+
+/*
+    Function that loads a texture from an image buffer held on the rust side. Returns the texture so it can be deleted later
+*/
+pub fn load_texture_from_image(gl: &WebGl2RenderingContext, image: &RgbaImage, textureNumber: u32) -> Result<WebGlTexture, RubixError>
 {
     //Get uint id for currently bound shader
-    let textureID = gl.create_texture().unwrap(); //.expect("Cannot create gl texture");
+    let textureID = gl.create_texture().ok_or(RubixError::GlObject("texture"))?;
+
+    //Load texture 0 + i for currently bound shader program
+    gl.active_texture(WebGl2RenderingContext::TEXTURE0 + textureNumber);
 
     //Set as active new texture id
     gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&textureID));
 
-    //Set this to true to flip texture image if upsidedown (Possibly Depreciated)
+    //Set this to true to flip texture image if upsidedown (Also applies to pixels uploaded from a byte buffer)
     gl.pixel_storei(WebGl2RenderingContext::UNPACK_FLIP_Y_WEBGL, true as i32);
 
-    //Load texture 0 + i for currently bound shader program
-    gl.active_texture(WebGl2RenderingContext::TEXTURE0 + textureNumber); 
-
     let target = WebGl2RenderingContext::TEXTURE_2D;
-    let level = 0; 
-    let internalformat = WebGl2RenderingContext::RGBA as i32; //How WebGL stores data   
-    let format = WebGl2RenderingContext::RGBA;                //Format of the data we are giving to WebGL 
-    let type_ = WebGl2RenderingContext::UNSIGNED_BYTE; 
+    let level = 0;
+    let internalformat = WebGl2RenderingContext::RGBA as i32; //How WebGL stores data
+    let width = image.width() as i32;
+    let height = image.height() as i32;
+    let border = 0;                                           //Must always be 0
+    let format = WebGl2RenderingContext::RGBA;                //Format of the data we are giving to WebGL
+    let type_ = WebGl2RenderingContext::UNSIGNED_BYTE;
 
     //Attach texture image (ie. localbuffer) to currently bound texture object (on GPU)
-    gl.tex_image_2d_with_u32_and_u32_and_html_image_element(target, level, internalformat, format, type_, htmlImg)?;
-    //Probably works //gl.tex_image_2d_with_u32_and_u32_and_html_canvas_element(target, level, internalformat, format, type_, source);
-    
+    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        target, level, internalformat, width, height, border, format, type_, Some(image.as_raw()))
+        .map_err(|e| RubixError::Texture(RubixError::from(e).to_string()))?;
+
     //Set the texture wrapping/filtering options (on the currently bound texture object)
     gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_S, WebGl2RenderingContext::REPEAT as i32);
     gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_T, WebGl2RenderingContext::REPEAT as i32);
@@ -40,5 +261,5 @@ pub fn load_texture_from_htmlImg(gl: &WebGl2RenderingContext, htmlImg: &HtmlImag
     //Generate mipmaps for texture (ie. makes smaller versions of itself for rendering distant versions of itself)
     gl.generate_mipmap(WebGl2RenderingContext::TEXTURE_2D);
 
-    Ok(())
+    Ok(textureID)
 }
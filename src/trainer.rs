@@ -0,0 +1,318 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::RubixError;
+use crate::history::History;
+use crate::notation::{Algorithm, Layers, Move};
+use crate::recognition::{zbll_cases, F2L_CASES, OLL_CASES, PLL_CASES};
+use crate::state::Face;
+
+/*
+    Trainer for drilling algorithm sets. Each case is a card scheduled with the SM-2 spaced repetition algorithm:
+    cases solved quickly come back after longer and longer intervals, while ones that are skipped come back the next day.
+    OLL, PLL, F2L and ZBLL are built in, where each ZBLL algorithm is done in two looks (the OCLL algorithm then PLL)
+    until the page adds its own algorithms for them. Other sets have no cases until the page adds them.
+    Progress is kept in localStorage
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const TRAINER_TYPES: &'static str = r#"
+/** Case added to the trainer, its algorithm solves the case (so undoing it sets the case up) */
+export interface TrainerCase { name: string; algorithm: string }
+
+/** Case in the trainer along with its spaced repetition progress. Due is a timestamp in milliseconds (as Date.now) */
+export interface TrainerCard extends TrainerCase {
+    set: string;
+    due: number;
+    interval: number;
+    repetitions: number;
+    ease: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "TrainerCase[]")]
+    pub type TrainerCases;
+
+    #[wasm_bindgen(typescript_type = "TrainerCard | undefined")]
+    pub type TrainerCard;
+
+    #[wasm_bindgen(typescript_type = "TrainerCard[]")]
+    pub type TrainerCards;
+}
+
+//Key progress is saved under in localStorage
+const STORAGE_KEY: &str = "rubix-trainer";
+
+//Length of a day in milliseconds, which intervals are counted in
+const DAY: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+//Sets which are last layer cases, so are set up from a random U turn to practise recognising them from any angle
+const LAST_LAYER_SETS: [&str; 3] = ["oll", "pll", "zbll"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Card {
+    pub set: String,
+    pub name: String,
+    pub algorithm: String,
+    pub due: f64,         // When the case should next be practised (milliseconds since the epoch)
+    pub interval: f64,    // Days between the last practice and the next
+    pub repetitions: u32, // Times in a row the case has been solved
+    pub ease: f64,        // How fast the interval grows (at least 1.3)
+}
+
+impl Card {
+
+    fn new(set: &str, name: &str, algorithm: &str) -> Self
+    {
+        Self { set: set.to_string(), name: name.to_string(), algorithm: algorithm.to_string(), due: 0.0, interval: 0.0, repetitions: 0, ease: 2.5 }
+    }
+
+    // Method to schedule the card given how well it went (from 0 to 5, where below 3 is a failure) using SM-2
+    fn review(&mut self, quality: u32, now: f64)
+    {
+        if quality < 3 {
+            self.repetitions = 0;
+            self.interval = 1.0;
+        } else {
+            self.repetitions += 1;
+            self.interval = match self.repetitions {
+                1 => 1.0,
+                2 => 6.0,
+                _ => (self.interval * self.ease).round(),
+            };
+        }
+
+        let miss = 5.0 - quality as f64;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(1.3);
+        self.due = now + self.interval * DAY;
+    }
+
+    pub fn to_js(&self) -> JsValue
+    {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| { js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).ok(); };
+
+        set("set", JsValue::from_str(&self.set));
+        set("name", JsValue::from_str(&self.name));
+        set("algorithm", JsValue::from_str(&self.algorithm));
+        set("due", JsValue::from_f64(self.due));
+        set("interval", JsValue::from_f64(self.interval));
+        set("repetitions", JsValue::from(self.repetitions));
+        set("ease", JsValue::from_f64(self.ease));
+
+        object.into()
+    }
+
+    fn from_js(value: &JsValue) -> Option<Self>
+    {
+        let get = |key: &str| js_sys::Reflect::get(value, &JsValue::from_str(key)).ok();
+        let string = |key: &str| get(key)?.as_string();
+        let number = |key: &str| get(key)?.as_f64();
+
+        Some(Self {
+            set: string("set")?,
+            name: string("name")?,
+            algorithm: string("algorithm")?,
+            due: number("due")?,
+            interval: number("interval")?,
+            repetitions: number("repetitions")? as u32,
+            ease: number("ease")?,
+        })
+    }
+}
+
+// Result of practising a case, sent to JS as a caseTrained event
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrainerResult {
+    pub card: Card,
+    pub solved: bool,
+    pub recognitionTime: f64, // Seconds from the case being set up to the first move
+    pub executionTime: f64,   // Seconds from the first move to the last
+}
+
+// Case currently set up on the cube
+struct Attempt {
+    card: usize,
+    setupTime: f64, // Time (in seconds, see get_current_time) the case was set up
+}
+
+#[derive(Default)]
+pub struct Trainer {
+    cards: Vec<Card>,
+    attempt: Option<Attempt>,
+}
+
+impl Trainer {
+
+    // Load progress saved in localStorage, adding cards for any built in cases which haven't been practised yet
+    pub fn load() -> Self
+    {
+        let mut trainer = Self::default();
+
+        let saved = storage().and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten());
+        if let Some(cards) = saved.and_then(|saved| js_sys::JSON::parse(&saved).ok()) {
+            trainer.cards = js_sys::Array::from(&cards).iter().filter_map(|card| Card::from_js(&card)).collect();
+        }
+
+        let builtIn = OLL_CASES.iter().enumerate().map(|(i, algorithm)| Card::new("oll", &format!("OLL {}", i + 1), algorithm))
+            .chain(PLL_CASES.iter().map(|(name, algorithm)| Card::new("pll", &format!("{} Perm", name), algorithm)))
+            .chain(F2L_CASES.iter().enumerate().map(|(i, algorithm)| Card::new("f2l", &format!("F2L {}", i + 1), algorithm)))
+            .chain(zbll_cases().iter().map(|(name, algorithm)| Card::new("zbll", name, algorithm)));
+
+        for card in builtIn {
+            if !trainer.cards.iter().any(|saved| saved.set == card.set && saved.name == card.name) {
+                trainer.cards.push(card);
+            }
+        }
+
+        trainer
+    }
+
+    // Save progress to localStorage (progress is only kept for this page if it can't be saved)
+    fn save(&self)
+    {
+        let cards = self.cards.iter().map(|card| card.to_js()).collect::<js_sys::Array>();
+        let saved = js_sys::JSON::stringify(&cards).ok().and_then(|json| json.as_string());
+
+        if let (Some(storage), Some(saved)) = (storage(), saved) {
+            if let Err(e) = storage.set_item(STORAGE_KEY, &saved) {
+                crate::error(&format!("Error: Trainer:: Unable to save progress {}", RubixError::from(e)));
+            }
+        }
+    }
+
+    pub fn cards(&self) -> &[Card]
+    {
+        &self.cards
+    }
+
+    // Add (or replace the algorithms of) cases in a set, keeping the progress of cases already in it
+    pub fn add_cases(&mut self, set: &str, cases: &[(String, String)]) -> Result<(), RubixError>
+    {
+        //Check every algorithm first, so nothing is added if one is invalid
+        for (_, algorithm) in cases {
+            Algorithm::parse(algorithm)?;
+        }
+
+        for (name, algorithm) in cases {
+            match self.cards.iter_mut().find(|card| card.set == set && card.name == *name) {
+                Some(card) => card.algorithm = algorithm.clone(),
+                None => self.cards.push(Card::new(set, name, algorithm)),
+            }
+        }
+
+        self.save();
+        Ok(())
+    }
+
+    /*
+        Method to pick the next case to practise from the given sets (or all sets): the most overdue case, else the next due.
+        Returns the card along with the moves which set it up on a solved cube. Skip any case already set up first
+    */
+    pub fn next(&mut self, sets: &[String], time: f64, mut random: impl FnMut() -> f64) -> Option<(Card, Vec<Move>)>
+    {
+        let (index, card) = self.cards.iter().enumerate()
+            .filter(|(_, card)| sets.is_empty() || sets.contains(&card.set))
+            .min_by(|(_, a), (_, b)| a.due.total_cmp(&b.due))?;
+
+        let mut setup = Algorithm::parse(&card.algorithm).ok()?.inverse().expand();
+
+        if LAST_LAYER_SETS.contains(&card.set.as_str()) {
+            let turns = (random() * 4.0) as i32 % 4;
+            setup.push(Move::new(Layers::Face(Face::U), turns));
+        }
+
+        self.attempt = Some(Attempt { card: index, setupTime: time });
+        Some((card.clone(), setup))
+    }
+
+    // Method to check if the case set up has been solved (called once the cube is solved). Solving quicker rates higher
+    pub fn finish(&mut self, history: &History, now: f64) -> Option<TrainerResult>
+    {
+        let attempt = self.attempt.take()?;
+
+        let first = history.moves.first().map_or(attempt.setupTime, |m| m.time);
+        let recognitionTime = first - attempt.setupTime;
+        let executionTime = history.duration();
+
+        let quality = match recognitionTime + executionTime {
+            t if t <= 3.0 => 5,
+            t if t <= 6.0 => 4,
+            _ => 3,
+        };
+
+        Some(self.review(attempt.card, quality, true, recognitionTime, executionTime, now))
+    }
+
+    // Method to forget the case set up (if there is one) without scheduling it, as the cube has been set up for something else
+    pub fn abandon(&mut self)
+    {
+        self.attempt = None;
+    }
+
+    // Method to give up on the case set up (if there is one), which counts as a failure
+    pub fn skip(&mut self, now: f64) -> Option<TrainerResult>
+    {
+        let attempt = self.attempt.take()?;
+        Some(self.review(attempt.card, 0, false, 0.0, 0.0, now))
+    }
+
+    fn review(&mut self, index: usize, quality: u32, solved: bool, recognitionTime: f64, executionTime: f64, now: f64) -> TrainerResult
+    {
+        self.cards[index].review(quality, now);
+        self.save();
+
+        TrainerResult { card: self.cards[index].clone(), solved, recognitionTime, executionTime }
+    }
+}
+
+fn storage() -> Option<web_sys::Storage>
+{
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::seeded_random;
+
+    #[test]
+    fn intervals_grow_while_solved_and_reset_when_failed()
+    {
+        let mut card = Card::new("oll", "OLL 1", OLL_CASES[0]);
+
+        card.review(4, 0.0);
+        assert_eq!((card.repetitions, card.interval), (1, 1.0));
+        card.review(4, 0.0);
+        assert_eq!((card.repetitions, card.interval), (2, 6.0));
+        assert_eq!(card.ease, 2.5);
+
+        //Interval grows by the ease, and a perfect answer makes it grow faster next time
+        card.review(5, 0.0);
+        assert_eq!(card.interval, 15.0);
+        assert!((card.ease - 2.6).abs() < 1e-9);
+        assert_eq!(card.due, 15.0 * DAY);
+
+        card.review(0, 0.0);
+        assert_eq!((card.repetitions, card.interval), (0, 1.0));
+        assert!(card.ease >= 1.3);
+    }
+
+    #[test]
+    fn abandoned_cases_are_not_scored()
+    {
+        let (name, algorithm) = PLL_CASES[0];
+        let mut trainer = Trainer { cards: vec![Card::new("pll", &format!("{} Perm", name), algorithm)], attempt: None };
+
+        let (card, setup) = trainer.next(&[], 10.0, seeded_random(42)).unwrap();
+        assert_eq!(card.name, "Aa Perm");
+        assert!(!setup.is_empty());
+
+        trainer.abandon();
+        assert!(trainer.finish(&History::default(), 0.0).is_none());
+        assert!(trainer.skip(0.0).is_none());
+        assert_eq!(trainer.cards()[0], card);
+    }
+}
@@ -0,0 +1,175 @@
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::RubixError;
+use crate::notation::{Algorithm, Axis, Layers, Move, Slice};
+use crate::state::{dot, quarter_turn, Face, IVec3};
+
+/*
+    Transforms of algorithms which keep how they were written (ie. their groups, commutators and conjugates),
+    so the result can be turned back into text: inverting, mirroring and re-targeting to be done from another side
+*/
+
+#[wasm_bindgen(typescript_custom_section)]
+const MIRROR_PLANE: &'static str = r#"
+/** Plane an algorithm is mirrored in: leftRight swaps L and R (ie. the M plane), frontBack swaps F and B (ie. the S plane) */
+export type MirrorPlane = "leftRight" | "frontBack";
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "MirrorPlane")]
+    pub type MirrorPlane;
+}
+
+/*
+ * Write an algorithm out in full, without groups, commutators or conjugates (eg. "[R, U]" becomes "R U R' U'")
+ */
+#[wasm_bindgen(js_name = expandAlgorithm)]
+pub fn expandAlgorithm(algorithm: &str) -> Result<String, JsValue>
+{
+    let moves = Algorithm::parse(algorithm)?.expand();
+    Ok(crate::notation::format_moves(&moves))
+}
+
+/*
+ * Get the algorithm which undoes the given one (eg. "[R, U] F" becomes "F' [U, R]")
+ */
+#[wasm_bindgen(js_name = invertAlgorithm)]
+pub fn invertAlgorithm(algorithm: &str) -> Result<String, JsValue>
+{
+    Ok(Algorithm::parse(algorithm)?.inverse().to_string())
+}
+
+/*
+ * Mirror an algorithm (eg. "R U R'" mirrored left to right is "L' U' L")
+ */
+#[wasm_bindgen(js_name = mirrorAlgorithm)]
+pub fn mirrorAlgorithm(algorithm: &str, plane: MirrorPlane) -> Result<String, JsValue>
+{
+    let axis = match plane.as_string().as_deref() {
+        Some("leftRight") => 0,
+        Some("frontBack") => 2,
+        _ => return Err(RubixError::InvalidOption("Mirror plane must be \"leftRight\" or \"frontBack\"".to_string()).into()),
+    };
+
+    Ok(Algorithm::parse(algorithm)?.map_moves(&|m| mirror_move(m, axis)).to_string())
+}
+
+/*
+ * Re-target an algorithm to be done after the given whole cube rotation, without needing to rotate the cube
+ * ie. get the moves which do the same as "rotation algorithm rotation'" (eg. "R U R'" with "y" becomes "B U B'")
+ */
+#[wasm_bindgen(js_name = rotateAlgorithm)]
+pub fn rotateAlgorithm(algorithm: &str, rotation: &str) -> Result<String, JsValue>
+{
+    let rotations = Algorithm::parse(rotation)?.expand();
+
+    if rotations.iter().any(|m| !matches!(m.layers, Layers::Rotation(_))) {
+        return Err(RubixError::InvalidAlgorithm(format!("\"{}\" must only be whole cube rotations (x, y and z)", rotation)).into());
+    }
+
+    Ok(Algorithm::parse(algorithm)?.map_moves(&|m| rotate_move(m, &rotations)).to_string())
+}
+
+// Mirror a move in the plane through the middle of the cube perpendicular to an axis (0 for x, 1 for y, 2 for z)
+pub fn mirror_move(m: Move, axis: usize) -> Move
+{
+    //Mirroring swaps the faces on either side of the plane and turns clockwise into counter-clockwise
+    move_to(m, |mut normal| { normal[axis] = -normal[axis]; normal }, true)
+}
+
+// Get the move which does the same as "rotations m rotations'"
+pub fn rotate_move(m: Move, rotations: &[Move]) -> Move
+{
+    move_to(m, |normal| {
+
+        //Face now on the side given was brought there by the rotations, so undo them (last first) to find where it started
+        rotations.iter().rev().fold(normal, |normal, rotation| {
+            let (face, _) = rotation.turn();
+            (0..rotation.quarterTurns).fold(normal, |normal, _| quarter_turn(normal, face.normal()))
+        })
+    }, false)
+}
+
+// Helper function to get the same kind of move (eg. a slice) turning around the face the given function takes its face to
+fn move_to(m: Move, to: impl Fn(IVec3) -> IVec3, reversed: bool) -> Move
+{
+    let (face, _) = m.turn();
+    let Some(newFace) = Face::from_normal(to(face.normal())) else { return m; };
+    let quarterTurns = if reversed { -m.quarterTurns } else { m.quarterTurns };
+
+    let layers = match m.layers {
+        Layers::Face(_) => return Move::new(Layers::Face(newFace), quarterTurns),
+        Layers::Wide(_) => return Move::new(Layers::Wide(newFace), quarterTurns),
+        Layers::Slice(_) => [Slice::M, Slice::E, Slice::S].map(Layers::Slice),
+        Layers::Rotation(_) => [Axis::X, Axis::Y, Axis::Z].map(Layers::Rotation),
+    };
+
+    //Slices and rotations are named after one face of their axis, so turn the other way if the new face is the opposite one
+    let (layers, named) = layers.into_iter()
+        .map(|layers| (layers, Move::new(layers, 1).turn().0))
+        .find(|(_, named)| dot(named.normal(), newFace.normal()) != 0)
+        .unwrap_or((m.layers, face));
+
+    Move::new(layers, quarterTurns * dot(named.normal(), newFace.normal()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_moves;
+    use crate::scramble::seeded_random;
+    use crate::state::CubeState;
+
+    fn mirrored(algorithm: &str, axis: usize) -> String
+    {
+        Algorithm::parse(algorithm).unwrap().map_moves(&|m| mirror_move(m, axis)).to_string()
+    }
+
+    fn rotated(algorithm: &str, rotation: &str) -> String
+    {
+        let rotations = Algorithm::parse(rotation).unwrap().expand();
+        Algorithm::parse(algorithm).unwrap().map_moves(&|m| rotate_move(m, &rotations)).to_string()
+    }
+
+    #[test]
+    fn mirrors_keep_grouping()
+    {
+        assert_eq!(mirrored("R U R'", 0), "L' U' L");
+        assert_eq!(mirrored("[R U R', D]", 0), "[L' U' L, D']");
+        assert_eq!(mirrored("F R2 M x", 2), "B' R2 M' x'");
+        assert_eq!(mirrored("(Rw U)2", 0), "(Lw' U')2");
+    }
+
+    #[test]
+    fn mirroring_twice_round_trips()
+    {
+        for algorithm in ["R U R' U'", "[F: [R, U]]2", "Rw M' E S2 x y z'", "(L' D B2)3'"] {
+            for axis in [0, 2] {
+                assert_eq!(mirrored(&mirrored(algorithm, axis), axis), algorithm);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_algorithms_do_the_same_as_rotating_the_cube()
+    {
+        assert_eq!(rotated("R U R'", "y"), "B U B'");
+
+        let mut random = seeded_random(40);
+        let algorithms = ["R U R' U'", "[Rw: M' E]", "F2 S' Dw x", "(L B' D2)2"];
+
+        for rotation in ["x", "y", "z", "y'", "x2", "x y", "z' y2"] {
+            let rotations = Algorithm::parse(rotation).unwrap().expand();
+            let undo = Algorithm::parse(rotation).unwrap().inverse().expand();
+
+            for algorithm in algorithms {
+                let start = CubeState::solved().apply_moves(&crate::scramble::random_scramble(10, &mut random));
+                let expected = start.apply_moves(&[rotations.clone(), parse_moves(algorithm).unwrap(), undo.clone()].concat());
+
+                assert_eq!(start.apply_moves(&parse_moves(&rotated(algorithm, rotation)).unwrap()), expected, "{} after {}", algorithm, rotation);
+            }
+        }
+    }
+}
@@ -1,33 +1,44 @@
-
 //https://github.com/cx20/webgl-test/blob/master/examples/rust/cube/src/lib.rs
 
-use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
 use web_sys::WebGl2RenderingContext;
+use crate::error::RubixError;
+
 /*
     Function to set up webGL Context
 */
-pub fn init_webgl_context(canvas_id: &str) -> Result<WebGl2RenderingContext, JsValue> {
-    
-    //Get a reference to the current window's document
-    let document = web_sys::window().unwrap().document().unwrap();
-
-    //Get a reference to the current window document's canvas
-    let canvas = document.get_element_by_id(canvas_id).unwrap();
-
-    //Dynamically cast element into rust canvas element
-    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+pub fn init_webgl_context(canvas: &web_sys::HtmlCanvasElement) -> Result<WebGl2RenderingContext, RubixError> {
 
     //Call to get reference to webGL context from canvas element
     let gl: WebGl2RenderingContext = canvas
         .get_context("webgl2")?              //HARD CODED STRING HERE <<<< WHY????
-        .unwrap()
+        .ok_or(RubixError::WebGl2Unsupported)?
         .dyn_into::<WebGl2RenderingContext>()
-        .unwrap();
+        .map_err(|_| RubixError::WebGl2Unsupported)?;
 
-    //Set webGL viewports size (Note: canvas width is hardcoded in canvas html element in index.html)
-    gl.viewport(0,0,canvas.width().try_into().unwrap(),canvas.height().try_into().unwrap());
+    //Set webGL viewports size (Note: kept up to date with the canvas size by the resize observer in app.rs)
+    gl.viewport(0, 0, canvas.width() as i32, canvas.height() as i32);
 
     Ok(gl)
-}
\ No newline at end of file
+}
+
+/*
+    Function to get a reference to the current window's document
+*/
+pub fn document() -> Result<web_sys::Document, RubixError> {
+
+    web_sys::window().and_then(|window| window.document()).ok_or(RubixError::NoDocument)
+}
+
+/*
+    Function to get an element of the document by id, dynamically cast into the expected rust element type
+*/
+pub fn get_element<T: JsCast>(id: &str, expected: &'static str) -> Result<T, RubixError> {
+
+    document()?
+        .get_element_by_id(id)
+        .ok_or_else(|| RubixError::MissingElement(id.to_string()))?
+        .dyn_into::<T>()
+        .map_err(|_| RubixError::WrongElementType { id: id.to_string(), expected })
+}
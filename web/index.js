@@ -1,19 +1,60 @@
-
-import init, { rubix_cube_simulation } from "../pkg/rubix.js";
+import init, { CubeApp } from "../pkg/rubix.js";
 
 const CANVAS_ID = "canvasID"; 
+const BUTTON_ID = "buttonID"; 
+const SCRAMBLE_ID = "scrambleID"; 
+const STATUS_ID = "statusID"; 
+const REPLAY_ID = "replayID"; 
 const canvas = document.getElementById(CANVAS_ID);
 
 async function run() {
   
   await init(); //Init() function auto generated when compiled?
 
-  //Set canvas heigth, width and border dim (can also set in index.html)
-  canvas.width = 600;
-  canvas.height = 400;
-  canvas.style = "border:25px solid #000000;";
+  //Canvas size is set in style.css, its drawing buffer is kept matching that size from the rust side
+  //Sticker colours (red, orange, yellow, green, blue, white) are optional, omit to use the embedded texture atlas
+  //Face images as data urls (in the same order) can be passed as faceImages instead to make a picture cube
+  //Setup failures (eg. no WebGL2, shader errors) are thrown as a RubixError describing the problem
+  try {
+    const cube = new CubeApp(canvas, {
+      stickerColours: ["#b71234", "#ff5800", "#ffd500", "#009b48", "#0046ad", "#ffffff"],
+      directionButton: document.getElementById(BUTTON_ID),
+    });
+
+    //Show what the cube is doing (see CubeEvent in the generated typescript definitions for every event)
+    const status = document.getElementById(STATUS_ID);
+    cube.on("scrambled", (event) => { status.textContent = `Scramble: ${event.scramble}`; });
+    cube.on("moveFinished", (event) => {
+      //Once F2L is solved, show which last layer case is left (and how to solve it)
+      const lastLayer = cube.recognizeCase();
+      const hint = lastLayer && lastLayer.stage !== "solved" ? ` (${lastLayer.name}: ${lastLayer.algorithm})` : "";
+      status.textContent = `Last move: ${event.move}${hint}`;
+    });
+    cube.on("solved", () => {
+      const { metrics } = cube.history();
+      status.textContent = `Solved! (${metrics.htm} HTM, ${metrics.qtm} QTM, ${metrics.stm} STM, ${metrics.etm} ETM)`;
+      console.log(`Reconstruction: ${cube.reconstructionUrl()}`);
+    });
+
+    document.getElementById(SCRAMBLE_ID).addEventListener("click", () => { cube.scramble(); canvas.focus(); });
+
+    //Replay the last solve (pressing again stops the replay and goes back to the cube as it was)
+    document.getElementById(REPLAY_ID).addEventListener("click", () => {
+      const solves = cube.solveRecords().length;
+      if (cube.replayStatus()) { cube.stopReplay(); }
+      else if (solves > 0) { cube.replay(solves - 1); }
+    });
+
+    //Load a reconstruction shared as an alg.cubing.net style link (eg. index.html?setup=R_U&alg=U-_R-)
+    const query = new URLSearchParams(location.search);
+    if (query.has("setup") || query.has("setup-alg") || query.has("alg")) { cube.loadReconstruction(location.search); }
 
-  rubix_cube_simulation();  
+    //Keys are only listened for while the cube has focus
+    canvas.focus();
+  } catch (e) {
+    console.error(`${e.name}: ${e.message}`);
+    canvas?.replaceWith(Object.assign(document.createElement("p"), { textContent: e.message }));
+  }
 }
 
 run(); 